@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
-use flint_utils::{Result, debug};
-use mlua::{Lua, Table};
+use flint_utils::{Result, debug, get_flag, info};
+use mlua::{Lua, Table, Value};
 
 mod ai;
 mod cmd;
@@ -104,6 +104,22 @@ pub fn add_ffi_modules(lua: &Lua) -> Result<()> {
     loaded.set("cmd", cmd)?;
     loaded.set("fs", fs)?;
 
+    // print() would end up in the middle of the JSON events, so it becomes a log line
+    if get_flag!(output).is_structured() {
+        lua.globals().set(
+            "print",
+            lua.create_function(|_, values: mlua::Variadic<Value>| {
+                let line = values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<mlua::Result<Vec<_>>>()?
+                    .join("\t");
+                info!("{}", line);
+                Ok(())
+            })?,
+        )?;
+    }
+
     // Custom module loader to allow our modules to work
     lua.load(
         r#"
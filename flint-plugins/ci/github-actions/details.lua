@@ -2,7 +2,17 @@ function Details()
     return {
         id = "github-actions",
         version = "1.0.0",
+        api_version = "^1.1",
         author = "Your Name",
         extensions = {} -- Output plugins don't need file extensions
     }
 end
+
+function Schema()
+    return {
+        branches = { type = "array", items = { type = "string" } },
+        pr_branches = { type = "array", items = { type = "string" } },
+        artifacts = { type = "string", default = "reports" },
+        on = { type = "table", description = "Overrides the generated workflow triggers" },
+    }
+end
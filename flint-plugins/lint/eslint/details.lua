@@ -3,6 +3,7 @@ function Details()
         id = "eslint",
         author = "Onkar Kapuskari",
         version = "0.0.1",
+        api_version = "^1.6",
         extensions = { "js", "ts", "mjs", "cjs" },
     }
     return details
@@ -19,3 +20,17 @@ function Dependencies()
         },
     }
 end
+
+-- Keys of [rules.common] that Generate() translates to eslint rules
+function CommonRules()
+    return {
+        max_line_length = { rules = { "@stylistic/max-len" } },
+        quote_style = { rules = { "@stylistic/quotes" } },
+        require_semicolons = { rules = { "@stylistic/semi" } },
+        indent = { rules = { "@stylistic/indent" } },
+        indent_style = { rules = { "@stylistic/indent" } },
+        indent_size = { rules = { "@stylistic/indent" } },
+        no_trailing_spaces = { rules = { "@stylistic/no-trailing-spaces" } },
+        no_multiple_empty_lines = { rules = { "@stylistic/no-multiple-empty-lines" } },
+    }
+end
@@ -3,16 +3,21 @@ local json = require("json")
 local js = require("js")
 
 
-local function getIndentRule(indent_config)
-    if not indent_config then return nil end
+local function getIndentRule(common)
+    -- Either an [rules.common.indent] table, or indent_style and indent_size
+    local indent_config = common.indent
+    if not indent_config then
+        if not common.indent_style and not common.indent_size then return nil end
+        indent_config = { indent_style = common.indent_style or "spaces", indent_size = common.indent_size or 4 }
+    end
 
     local indent_value = indent_config.indent_style == "spaces" and indent_config.indent_size or "tab"
 
     local options = js.object({
         SwitchCase = indent_config.switch_case,
         FunctionDeclaration = js.object({
-            body = indent_config.function_declaration.body,
-            parameters = indent_config.function_declaration.parameters
+            body = indent_config.function_declaration and indent_config.function_declaration.body,
+            parameters = indent_config.function_declaration and indent_config.function_declaration.parameters
         }),
         ignoredNodes = indent_config.ignore_nodes,
         ignoreComments = indent_config.ignore_comments,
@@ -67,6 +72,15 @@ local function getSemiRule(require_semicolons)
     }
 end
 
+local function getMaxLenRule(max_line_length)
+    if not max_line_length then return nil end
+
+    return {
+        name = "max-len",
+        value = js.object({ code = max_line_length })
+    }
+end
+
 function Generate(config)
     local common = config.common
     local extra = config.config
@@ -100,7 +114,7 @@ function Generate(config)
     local rules = js.object({})
 
     -- Add each rule that has a value
-    local indentRule = getIndentRule(common.indent)
+    local indentRule = getIndentRule(common)
     if indentRule then
         rules["@stylistic/" .. indentRule.name] = js.array("error", indentRule.value, indentRule.options)
     end
@@ -115,6 +129,19 @@ function Generate(config)
         rules["@stylistic/" .. semiRule.name] = js.array("error", semiRule.value)
     end
 
+    local maxLenRule = getMaxLenRule(common.max_line_length)
+    if maxLenRule then
+        rules["@stylistic/" .. maxLenRule.name] = js.array("error", maxLenRule.value)
+    end
+
+    if common.no_trailing_spaces ~= nil then
+        rules["@stylistic/no-trailing-spaces"] = common.no_trailing_spaces and "error" or "off"
+    end
+
+    if common.no_multiple_empty_lines ~= nil then
+        rules["@stylistic/no-multiple-empty-lines"] = common.no_multiple_empty_lines and "error" or "off"
+    end
+
     -- Add rules table to eslint config
     eslintConfig.rules = rules
 
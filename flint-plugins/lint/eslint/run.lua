@@ -6,6 +6,11 @@ function Run(config)
     local extra = config.config
     local args = { "npx", "eslint", "--format", "json" }
 
+    -- Only set by `flint test --changed`, otherwise eslint lints the whole project
+    for _, file in ipairs(config.files or {}) do
+        table.insert(args, file)
+    end
+
     return args
 end
 
@@ -30,7 +35,7 @@ function Eval(output)
                 for _, msg in ipairs(file_result.messages) do
                     local result = {
                         file_name = path.relative(file_path, cwd),
-                        -- rule_id = msg.ruleId,
+                        rule_id = msg.ruleId,
                         line_no = msg.line,
                         column_no = msg.column,
                         error_message = msg.message,
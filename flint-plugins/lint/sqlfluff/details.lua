@@ -5,6 +5,7 @@ function Details()
         id = "sqlfluff",
         author = "Onkar Kapuskari",
         version = "0.0.1",
+        api_version = "^1.6",
         extensions = { "sql" },
     }
     return details
@@ -23,3 +24,17 @@ function Dependencies()
         },
     }
 end
+
+-- Keys of [rules.common] that Generate() translates to sqlfluff settings
+function CommonRules()
+    return {
+        max_line_length = { rules = { "layout.long_lines" } },
+        indent_style = { rules = { "layout.indent" } },
+        indent_size = { rules = { "layout.indent" } },
+        no_trailing_spaces = {
+            support = "partial",
+            rules = { "layout.spacing" },
+            note = "trailing whitespace is always an error in sqlfluff, it can't be allowed",
+        },
+    }
+end
@@ -1,8 +1,26 @@
 local toml = require("toml")
 
 function Generate(config)
+    local common = config.common or {}
     config.common = nil
 
+    -- Settings in [rules.sqlfluff] win over the common ones
+    if common.max_line_length then
+        config.rules = config.rules or {}
+        if config.rules.max_line_length == nil then
+            config.rules.max_line_length = common.max_line_length
+        end
+    end
+    if common.indent_style or common.indent_size then
+        config.indentation = config.indentation or {}
+        if common.indent_style and config.indentation.indent_unit == nil then
+            config.indentation.indent_unit = common.indent_style == "tabs" and "tab" or "space"
+        end
+        if common.indent_size and config.indentation.tab_space_size == nil then
+            config.indentation.tab_space_size = common.indent_size
+        end
+    end
+
     local output = { tool = {} }
     output.tool.sqlfluff = config
 
@@ -2,7 +2,23 @@ function Details()
     return {
         id = "ai",
         version = "1.0.0",
+        api_version = "^1.1",
         author = "Soham Karandikar",
         extensions = {} -- Output plugins don't need file extensions
     }
 end
+
+function Schema()
+    return {
+        output_path = { type = "string", default = "reports" },
+        env = {
+            type = "table",
+            required = true,
+            fields = {
+                API_BASE_URL = { type = "string", required = true },
+                API_KEY = { type = "string", required = true },
+                MODEL = { type = "string", required = true },
+            },
+        },
+    }
+end
@@ -2,7 +2,24 @@ function Details()
     return {
         id = "db",
         version = "1.0.0",
+        api_version = "^1.1",
         author = "Soham Karandikar",
         extensions = {}
     }
 end
+
+function Schema()
+    return {
+        env = {
+            type = "table",
+            required = true,
+            fields = {
+                host = { type = "string", required = true },
+                port = { type = "string", required = true },
+                username = { type = "string", required = true },
+                password = { type = "string", required = true },
+                database = { type = "string", required = true },
+            },
+        },
+    }
+end
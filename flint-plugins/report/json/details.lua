@@ -2,7 +2,14 @@ function Details()
     return {
         id = "json",
         version = "1.0.0",
+        api_version = "^1.1",
         author = "Soham Karandikar",
         extensions = {} -- Output plugins don't need file extensions
     }
 end
+
+function Schema()
+    return {
+        output_path = { type = "string", default = "reports" },
+    }
+end
@@ -3,6 +3,7 @@ function Details()
         id = "jest",
         author = "Aditya Sakhare (test)",
         version = "0.0.1",
+        api_version = "^1.1",
         extensions = { "js", "ts", "mjs", "cjs" },
     }
     return details
@@ -15,3 +16,16 @@ function Dependencies()
         },
     }
 end
+
+function Schema()
+    return {
+        enabled = { type = "boolean", default = true },
+        config_path = { type = "string", description = "Where the generated jest config is written" },
+        test_environment = { type = "string", enum = { "node", "jsdom" }, default = "node" },
+        verbose = { type = "boolean", default = true },
+        collect_coverage = { type = "boolean", default = false },
+        root_dir = { type = "string" },
+        include = { type = "array", items = { type = "string" } },
+        exclude = { type = "array", items = { type = "string" } },
+    }
+end
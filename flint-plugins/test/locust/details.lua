@@ -3,6 +3,7 @@ function Details()
         id = "locust",
         author = "Soham Karandikar",
         version = "0.0.1",
+        api_version = "^1.1",
         extensions = { "*" },
     }
     return details
@@ -15,3 +16,25 @@ function Dependencies()
         },
     }
 end
+
+function Schema()
+    return {
+        locustfile = { type = "string", required = true },
+        host = { type = "string", required = true },
+        users = { type = "integer", default = 100 },
+        spawn_rate = { type = "number", default = 1 },
+        run_time = { type = "string", default = "20s" },
+        output_path = { type = "string", required = true },
+        env = {
+            type = "table",
+            required = true,
+            fields = {
+                host = { type = "string", required = true },
+                port = { type = "string", required = true },
+                username = { type = "string", required = true },
+                password = { type = "string", required = true },
+                database = { type = "string", required = true },
+            },
+        },
+    }
+end
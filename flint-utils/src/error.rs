@@ -1,4 +1,4 @@
-use std::{error::Error as ErrorTrait, sync::Arc};
+use std::{error::Error as ErrorTrait, path::PathBuf, sync::Arc};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,6 +26,44 @@ pub enum AppError {
 
     #[error("Environment error: {0}")]
     Env(#[from] dotenvy::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("No config found for plugin {plugin_id}: expected a [{section}.{plugin_id}] table in {}", file.display())]
+    PluginConfigMissing {
+        plugin_id: String,
+        section: String,
+        file: PathBuf,
+    },
+
+    #[error("Failed to load plugin {plugin_id} ({}):\n{traceback}", file.display())]
+    PluginLoad {
+        plugin_id: String,
+        file: PathBuf,
+        traceback: String,
+    },
+
+    #[error("Plugin {plugin_id} ({}) failed:\n{traceback}", file.display())]
+    PluginRuntime {
+        plugin_id: String,
+        file: PathBuf,
+        traceback: String,
+    },
+
+    #[error("Plugin {plugin_id} ({}) is not compatible with this version of Flint: {message}", file.display())]
+    PluginIncompatible {
+        plugin_id: String,
+        file: PathBuf,
+        message: String,
+    },
+
+    #[error("Plugin {plugin_id} ({}) does not follow the plugin API: {message}", file.display())]
+    PluginContract {
+        plugin_id: String,
+        file: PathBuf,
+        message: String,
+    },
 }
 
 // Convert Box<dyn Error> to AppError using a catch-all approach
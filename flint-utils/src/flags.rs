@@ -5,6 +5,8 @@ use std::{
 
 use directories::ProjectDirs;
 
+use crate::output::OutputFormat;
+
 pub fn plugin_dir() -> PathBuf {
     if cfg!(debug_assertions) {
         return PathBuf::from("./flint-plugins");
@@ -19,12 +21,26 @@ pub fn plugin_dir() -> PathBuf {
     }
 }
 
+/// Downloaded plugin repos are cached here and shared between projects.
+/// Set `FLINT_CACHE_DIR` to use a different location (e.g. one primed on another machine).
+pub fn cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("FLINT_CACHE_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    match ProjectDirs::from("com", "Flint", "flint") {
+        Some(proj_dirs) => proj_dirs.data_dir().join("cache"),
+        None => std::env::temp_dir().join("flint-cache"),
+    }
+}
+
 pub struct Flags {
     pub non_interactive: bool,
     pub plugins_dir: PathBuf,
     pub config_path: PathBuf,
     pub current_dir: PathBuf,
     pub no_install: bool,
+    pub output: OutputFormat,
 }
 
 // Create a static global instance with RwLock
@@ -35,6 +51,7 @@ pub static GLOBAL_FLAGS: LazyLock<RwLock<Flags>> = LazyLock::new(|| {
         config_path: std::env::current_dir().unwrap().join("flint.toml"),
         current_dir: std::env::current_dir().unwrap(),
         no_install: false,
+        output: OutputFormat::Text,
     })
 });
 
@@ -2,6 +2,7 @@ pub mod env;
 pub mod error;
 pub mod flags;
 pub mod logs;
+pub mod output;
 
 pub use error::{AppError as Error, AppResult as Result};
 
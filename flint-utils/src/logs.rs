@@ -1,6 +1,8 @@
 use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use crate::get_flag;
+use serde_json::json;
+
+use crate::{get_flag, output};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub enum LogKind {
@@ -55,7 +57,13 @@ pub fn add_log(kind: LogKind, message: String) {
     let is_non_interactive = get_flag!(non_interactive);
 
     let log = format!("{} {}", prefix, message);
-    if is_non_interactive {
+    if is_non_interactive && get_flag!(output).is_structured() {
+        output::emit(json!({
+            "event": "log",
+            "level": prefix.trim_matches(['[', ']', ':']),
+            "message": message,
+        }));
+    } else if is_non_interactive {
         println!("{}", log);
     }
     writeln!(file, "{}", log).unwrap();
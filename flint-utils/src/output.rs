@@ -0,0 +1,63 @@
+use serde_json::{Map, Value, json};
+use std::sync::Mutex;
+
+use crate::get_flag;
+
+/// How non-interactive commands print what they are doing
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// `[info]:` style log lines
+    #[default]
+    Text,
+    /// A single JSON document with every event and the summary, printed at the end
+    Json,
+    /// One JSON event per line, as it happens
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// Whether events are printed instead of log lines
+    pub fn is_structured(&self) -> bool {
+        *self != OutputFormat::Text
+    }
+}
+
+/// Events of a `--output json` run, printed together with the summary
+static EVENTS: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// Prints an event (an object with an `event` field) in the structured output modes.
+/// Does nothing with text output, or when the TUI is running.
+pub fn emit(event: Value) {
+    if !get_flag!(non_interactive) {
+        return;
+    }
+    match get_flag!(output) {
+        OutputFormat::Text => (),
+        OutputFormat::Json => EVENTS.lock().unwrap().push(event),
+        OutputFormat::Ndjson => println!("{}", event),
+    }
+}
+
+/// Prints the summary of a run. With ndjson it is the last event, with json it is
+/// printed along with every event that came before it.
+pub fn finish(summary: Map<String, Value>) {
+    match get_flag!(output) {
+        OutputFormat::Text => (),
+        OutputFormat::Json => {
+            let events = std::mem::take(&mut *EVENTS.lock().unwrap());
+            println!(
+                "{}",
+                json!({
+                    "events": events,
+                    "summary": summary,
+                })
+            );
+        }
+        OutputFormat::Ndjson => {
+            let mut event = Map::new();
+            event.insert("event".into(), "summary".into());
+            event.extend(summary);
+            println!("{}", Value::Object(event));
+        }
+    }
+}
@@ -1,15 +1,18 @@
+use super::check_config::{CheckConfigArgs, CheckConfigWidget};
 use super::generate::{GenerateWidget, GenerateWidgetArgs};
 use super::help::HelpWidget;
 use super::init::{InitWidget, InitWidgetArgs};
-use super::install::{InstallArgs, InstallWidget};
-use super::test::{TestArgs, TestWidget};
+use super::install::{InstallArgs, InstallWidget, UpdateArgs};
+use super::plugin::{PluginArgs, PluginWidget};
+use super::status::{StatusArgs, StatusWidget};
+use super::test::{BaselineArgs, TestArgs, TestWidget};
 use super::AppWidget;
 use crate::util::handle_key_events;
 use clap::{Parser, Subcommand};
 use crossterm::event;
 use crossterm::event::KeyCode;
 use flint_macros::{ui, widget};
-use flint_utils::{error, Error, Result};
+use flint_utils::{error, output::OutputFormat, Error, Result};
 use ratatui::widgets::WidgetRef;
 use ratatui::{prelude::*, DefaultTerminal};
 use std::io;
@@ -39,6 +42,10 @@ pub struct AppArgs {
     #[clap(long, default_value_t = false, global = false)]
     pub no_install: bool,
 
+    /// Print JSON events instead of log lines, for scripts and editors
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text, global = false)]
+    pub output: OutputFormat,
+
     #[command(subcommand)]
     pub command: Option<AppWidgetArgs>,
 }
@@ -52,11 +59,39 @@ pub enum AppWidgetArgs {
     Generate(GenerateWidgetArgs),
     /// Tests a flint project
     Test(TestArgs),
+    /// Saves the current lint issues to flint-baseline.json, so only new ones fail `flint test`
+    Baseline(BaselineArgs),
     /// Installs the given list of plugins
     Install(InstallArgs),
+    /// Updates plugins to the latest commit and records them in flint.lock
+    Update(UpdateArgs),
+    /// Validates flint.toml against the config schemas declared by plugins
+    CheckConfig(CheckConfigArgs),
+    /// Lists generated files that are up to date, edited by hand, stale or orphaned
+    Status(StatusArgs),
+    /// Tools for writing plugins
+    Plugin(PluginArgs),
     Help,
 }
 
+impl AppWidgetArgs {
+    /// The subcommand as typed on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppWidgetArgs::Init(_) => "init",
+            AppWidgetArgs::Generate(_) => "generate",
+            AppWidgetArgs::Test(_) => "test",
+            AppWidgetArgs::Baseline(_) => "baseline",
+            AppWidgetArgs::Install(_) => "install",
+            AppWidgetArgs::Update(_) => "update",
+            AppWidgetArgs::CheckConfig(_) => "check-config",
+            AppWidgetArgs::Status(_) => "status",
+            AppWidgetArgs::Plugin(_) => "plugin",
+            AppWidgetArgs::Help => "help",
+        }
+    }
+}
+
 impl App {
     pub fn new(args: AppArgs) -> Self {
         let (sender, receiver) = mpsc::channel();
@@ -75,9 +110,14 @@ impl App {
 
         self.active_widget = match args.command.unwrap() {
             AppWidgetArgs::Install(args) => Box::new(InstallWidget::new(args)),
+            AppWidgetArgs::Update(args) => Box::new(InstallWidget::update(args)),
             AppWidgetArgs::Generate(args) => Box::new(GenerateWidget::new(args)),
             AppWidgetArgs::Test(args) => Box::new(TestWidget::new(args)),
+            AppWidgetArgs::Baseline(args) => Box::new(TestWidget::baseline(args)),
             AppWidgetArgs::Init(args) => Box::new(InitWidget::new(args)),
+            AppWidgetArgs::CheckConfig(args) => Box::new(CheckConfigWidget::new(args)),
+            AppWidgetArgs::Status(args) => Box::new(StatusWidget::new(args)),
+            AppWidgetArgs::Plugin(args) => Box::new(PluginWidget::new(args)),
             _ => Box::new(HelpWidget::default()),
         };
 
@@ -0,0 +1,408 @@
+use std::{cell::RefCell, collections::BTreeSet, ops::Range, path::Path};
+
+use clap::Parser;
+use crossterm::event::{KeyCode, MouseEventKind};
+use flint_macros::ui;
+use flint_utils::{error, get_flag, success, warn, Result};
+use ratatui::prelude::*;
+use ratatui::widgets::WidgetRef;
+use toml_edit::{ImDocument, Table};
+
+use super::AppWidget;
+use crate::{
+    plugin::{
+        self,
+        schema::{check_table, closest_match, reserved_fields, Diagnostic, Severity},
+        Plugin, PluginKind,
+    },
+    util::{handle_key_events, handle_mouse_event, toml::Config},
+    widgets::logs::{LogsState, LogsWidget},
+};
+
+#[derive(Debug)]
+pub struct CheckConfigWidget {
+    logs: LogsWidget,
+    logs_state: RefCell<LogsState>,
+    errors: usize,
+    args: CheckConfigArgs,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct CheckConfigArgs {
+    /// Show help for the check-config command
+    #[clap(short, long)]
+    help: bool,
+
+    /// Treat warnings (e.g. unknown keys) as errors
+    #[clap(long)]
+    strict: bool,
+}
+
+impl CheckConfigWidget {
+    pub fn new(args: CheckConfigArgs) -> Self {
+        Self {
+            logs: LogsWidget,
+            logs_state: RefCell::new(LogsState::default()),
+            errors: 0,
+            args,
+        }
+    }
+}
+
+const SECTIONS: [(&str, PluginKind); 4] = [
+    ("rules", PluginKind::Lint),
+    ("tests", PluginKind::Test),
+    ("ci", PluginKind::Ci),
+    ("report", PluginKind::Report),
+];
+
+/// Validates the contents of a flint.toml file against the config schemas of the installed plugins
+pub fn check_config(contents: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let doc = match ImDocument::parse(contents) {
+        Ok(doc) => doc,
+        Err(err) => {
+            diagnostics.push(Diagnostic::error(err.message(), err.span()));
+            return diagnostics;
+        }
+    };
+
+    // Catches problems with the [flint] table and the overall shape of the file
+    if let Err(err) = toml::from_str::<Config>(contents) {
+        diagnostics.push(Diagnostic::error(err.message(), err.span()));
+        return diagnostics;
+    }
+
+    match plugin::list() {
+        Ok(plugins) => check_plugin_tables(doc.as_table(), plugins, &mut diagnostics),
+        Err(err) => diagnostics.push(Diagnostic::error(
+            format!("unable to load plugins: {}", err),
+            None,
+        )),
+    }
+
+    diagnostics
+}
+
+/// Checks the `[rules]`, `[tests]`, `[ci]`, `[report]` and `[config]` tables of a
+/// parsed flint.toml against `plugins`
+fn check_plugin_tables(
+    root: &Table,
+    plugins: &BTreeSet<Plugin>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (section, kind) in SECTIONS {
+        let Some(section_table) = root.get(section).and_then(|item| item.as_table_like()) else {
+            continue;
+        };
+
+        for (id, item) in section_table.iter() {
+            if section == "rules" && id == "common" {
+                continue;
+            }
+
+            let path = format!("{}.{}", section, id);
+            let key_span = section_table
+                .get_key_value(id)
+                .and_then(|(key, _)| key.span());
+
+            let Some(plugin) = plugins
+                .iter()
+                .find(|plugin| plugin.kind == kind && plugin.details.id == id)
+            else {
+                let mut message = format!("unknown {} plugin `{}`", kind.to_string(), id);
+                let same_kind = plugins
+                    .iter()
+                    .filter(|plugin| plugin.kind == kind)
+                    .map(|plugin| &plugin.details.id);
+
+                if let Some(other) = plugins.iter().find(|plugin| plugin.details.id == id) {
+                    message.push_str(&format!(
+                        ", `{}` is a {} plugin",
+                        id,
+                        other.kind.to_string()
+                    ));
+                } else if let Some(suggestion) = closest_match(id, same_kind) {
+                    message.push_str(&format!(", did you mean `{}`?", suggestion));
+                } else {
+                    message.push_str(" (is it installed?)");
+                }
+
+                diagnostics.push(Diagnostic::error(message, key_span));
+                continue;
+            };
+
+            let Some(table) = item.as_table_like() else {
+                diagnostics.push(Diagnostic::error(
+                    format!("`{}` should be a table", path),
+                    item.span().or(key_span),
+                ));
+                continue;
+            };
+
+            match plugin.get_schema() {
+                Ok(Some(mut schema)) => {
+                    schema.extend(reserved_fields());
+                    check_table(table, item.span(), &schema, &path, diagnostics)
+                }
+                Ok(None) => (),
+                Err(err) => diagnostics.push(Diagnostic::error(
+                    format!("unable to load config schema for plugin `{}`: {}", id, err),
+                    key_span,
+                )),
+            }
+        }
+    }
+
+    // [config.<id>] only applies to lint plugins configured under [rules.<id>]
+    if let Some(extra) = root.get("config").and_then(|item| item.as_table_like()) {
+        let rules = root.get("rules").and_then(|item| item.as_table_like());
+        for (id, _) in extra.iter() {
+            if rules.and_then(|rules| rules.get(id)).is_none() {
+                let mut message = format!("`config.{}` has no matching `[rules.{}]` table", id, id);
+                let linter_ids = rules
+                    .map(|rules| {
+                        rules
+                            .iter()
+                            .map(|(id, _)| id.to_string())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                if let Some(suggestion) = closest_match(id, &linter_ids) {
+                    message.push_str(&format!(", did you mean `{}`?", suggestion));
+                }
+
+                diagnostics.push(Diagnostic::warning(
+                    message,
+                    extra.get_key_value(id).and_then(|(key, _)| key.span()),
+                ));
+            }
+        }
+    }
+}
+
+fn line_col(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let col = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, col)
+}
+
+/// Formats a diagnostic along with the offending line, similar to rustc
+pub fn render_diagnostic(diagnostic: &Diagnostic, contents: &str, file_name: &str) -> String {
+    let Some(Range { start, end }) = diagnostic.span.clone() else {
+        return format!("{}\n  --> {}", diagnostic.message, file_name);
+    };
+
+    let (line, col) = line_col(contents, start);
+    let source_line = contents.lines().nth(line - 1).unwrap_or_default();
+    let width = contents[start..end.min(contents.len())]
+        .lines()
+        .next()
+        .map(|s| s.chars().count())
+        .unwrap_or(0)
+        .max(1);
+    let gutter = " ".repeat(line.to_string().len());
+
+    format!(
+        "{}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}",
+        diagnostic.message,
+        gutter,
+        file_name,
+        line,
+        col,
+        gutter,
+        line,
+        source_line,
+        gutter,
+        " ".repeat(col - 1),
+        "^".repeat(width)
+    )
+}
+
+impl AppWidget for CheckConfigWidget {
+    fn setup(&mut self) -> Result<()> {
+        let config_path = get_flag!(config_path);
+        let contents = std::fs::read_to_string(&config_path)?;
+        let file_name = Path::new(&config_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| config_path.display().to_string());
+
+        let diagnostics = check_config(&contents);
+        let mut warnings = 0;
+
+        for diagnostic in &diagnostics {
+            let rendered = render_diagnostic(diagnostic, &contents, &file_name);
+            match diagnostic.severity {
+                Severity::Error => {
+                    self.errors += 1;
+                    error!("{}", rendered);
+                }
+                Severity::Warning => {
+                    warnings += 1;
+                    warn!("{}", rendered);
+                }
+            }
+        }
+
+        if self.args.strict {
+            self.errors += warnings;
+        }
+
+        if self.errors == 0 {
+            success!("{} is valid ({} warnings)", file_name, warnings);
+        } else {
+            error!(
+                "{} has {} errors and {} warnings",
+                file_name, self.errors, warnings
+            );
+        }
+
+        Ok(())
+    }
+
+    fn handle_events(&mut self, event: crossterm::event::Event) -> Result<()> {
+        let _ = handle_key_events(event.clone(), |_, key_code| match key_code {
+            KeyCode::Up => {
+                self.logs_state.borrow_mut().scroll_up(1);
+                Ok(())
+            }
+            KeyCode::Down => {
+                self.logs_state.borrow_mut().scroll_down(1);
+                Ok(())
+            }
+            _ => Ok(()),
+        });
+
+        handle_mouse_event(event.clone(), |mouse_event| match mouse_event {
+            MouseEventKind::ScrollUp => {
+                self.logs_state.borrow_mut().scroll_up(1);
+                Ok(())
+            }
+            MouseEventKind::ScrollDown => {
+                self.logs_state.borrow_mut().scroll_down(1);
+                Ok(())
+            }
+            _ => Ok(()),
+        })
+    }
+
+    fn exit_code(&self) -> i32 {
+        if self.errors > 0 {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl WidgetRef for CheckConfigWidget {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let mut logs_state = self.logs_state.borrow_mut();
+        ui!((area, buf) => {
+            Stateful(&mut logs_state) {
+                { self.logs }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::testing::write_plugin;
+    use mlua::Lua;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    const SCHEMA: &str = r#"
+function Schema()
+    return {
+        severity = { type = "string", enum = { "warn", "error" }, default = "warn" },
+        max_len = { type = "integer", default = 80 },
+    }
+end
+"#;
+
+    fn x_lint() -> (TempDir, BTreeSet<Plugin>) {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("x-lint");
+        write_plugin(&dir, "x-lint", "1.0.0");
+        let mut details = OpenOptions::new()
+            .append(true)
+            .open(dir.join("details.lua"))
+            .unwrap();
+        details.write_all(SCHEMA.as_bytes()).unwrap();
+
+        let plugin = plugin::load_plugin(&Lua::new(), dir, PluginKind::Lint).unwrap();
+        (tmp, BTreeSet::from([plugin]))
+    }
+
+    fn check(rules: &str) -> Vec<(Severity, String)> {
+        let (_tmp, plugins) = x_lint();
+        let contents = format!("[rules.x-lint]\n{}", rules);
+        let doc = ImDocument::parse(contents.as_str()).unwrap();
+        let mut diagnostics = Vec::new();
+        check_plugin_tables(doc.as_table(), &plugins, &mut diagnostics);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.message))
+            .collect()
+    }
+
+    #[test]
+    fn suggests_a_key_for_a_typo() {
+        assert_eq!(
+            check("severty = \"error\"\n"),
+            vec![(
+                Severity::Warning,
+                "unknown key `rules.x-lint.severty`, did you mean `severity`?".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_type() {
+        assert_eq!(
+            check("max_len = \"120\"\nseverity = \"fatal\"\n"),
+            vec![
+                (
+                    Severity::Error,
+                    "`rules.x-lint.max_len` should be of type integer, found string".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`rules.x-lint.severity` must be one of \"warn\", \"error\", found \"fatal\""
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_fields_flint_reads_itself() {
+        assert!(check("timeout = 30\nafter = [\"y-lint\"]\nexclude = [\"dist/**\"]\n").is_empty());
+        assert_eq!(
+            check("timeout = \"soon\"\n"),
+            vec![(
+                Severity::Error,
+                "`rules.x-lint.timeout` should be of type number, found string".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn fills_in_defaults_from_the_schema() {
+        let (_tmp, plugins) = x_lint();
+        let plugin = plugins.first().unwrap();
+        let toml: Config =
+            toml::from_str("[flint]\nversion = 1\n[rules.x-lint]\nmax_len = 120\n").unwrap();
+
+        let config = plugin.resolved_config(&toml).unwrap();
+        assert_eq!(config["severity"].as_str(), Some("warn"));
+        assert_eq!(config["max_len"].as_integer(), Some(120));
+    }
+}
@@ -1,15 +1,33 @@
 use super::AppWidget;
 use crate::{
-    plugin::{self, Plugin},
-    util::toml::Config,
-    widgets::logs::LogsWidget,
+    plugin::{self, common::CommonRulesMatrix, generated::GeneratedFiles, Plugin},
+    util::{
+        events::{self, Event},
+        handle_key_events, handle_mouse_event,
+        toml::Config,
+    },
+    widgets::{
+        logs::{LogsState, LogsWidget},
+        review::{Decision, ReviewFile, ReviewState, ReviewWidget},
+    },
 };
 use clap::Parser;
+use crossterm::event::{KeyCode, MouseEventKind};
 use flint_macros::ui;
-use flint_utils::{error, get_flag, info, success, Result};
+use flint_utils::{error, get_flag, info, logs::get_logs, success, warn, Result};
 use ratatui::prelude::*;
-use ratatui::widgets::WidgetRef;
-use std::{fs, sync::Arc};
+use ratatui::widgets::{Paragraph, WidgetRef};
+use serde_json::{json, Map, Value};
+use similar::TextDiff;
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 use threadpool::ThreadPool;
 
 #[allow(unused)]
@@ -18,6 +36,19 @@ pub struct GenerateWidget {
     thread_pool: Option<ThreadPool>,
     logs_widget: LogsWidget,
     args: GenerateWidgetArgs,
+    failed: Arc<AtomicBool>,
+    /// Files whose contents differ from what would be generated, with --dry-run or --check
+    outdated: Arc<Mutex<Vec<PathBuf>>>,
+    /// Files edited since they were generated, which weren't overwritten
+    drifted: Arc<Mutex<Vec<PathBuf>>>,
+    /// What was written by earlier runs, updated as files are written
+    generated: Arc<Mutex<GeneratedFiles>>,
+    /// What the configured linters do with [rules.common]
+    common_rules: Option<CommonRulesMatrix>,
+    /// Files waiting to be accepted or rejected in the TUI
+    review: Arc<Mutex<Vec<ReviewFile>>>,
+    review_state: RefCell<ReviewState>,
+    logs_state: RefCell<LogsState>,
 }
 
 #[derive(Parser, Clone)]
@@ -25,6 +56,25 @@ pub struct GenerateWidgetArgs {
     /// Show help for the generate command
     #[clap(short, long)]
     help: bool,
+
+    /// Show a diff of every file that would change, without writing anything
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Exit with 1 if any generated file is out of date, without writing anything
+    #[clap(long)]
+    check: bool,
+
+    /// Overwrite files that were edited since they were generated
+    #[clap(long)]
+    force: bool,
+}
+
+impl GenerateWidgetArgs {
+    /// Whether files are only compared, not written
+    pub fn writes_nothing(&self) -> bool {
+        self.dry_run || self.check
+    }
 }
 
 impl GenerateWidget {
@@ -34,49 +84,266 @@ impl GenerateWidget {
             thread_pool: None,
             logs_widget: LogsWidget::default(),
             args,
+            failed: Arc::new(AtomicBool::new(false)),
+            outdated: Arc::new(Mutex::new(Vec::new())),
+            drifted: Arc::new(Mutex::new(Vec::new())),
+            generated: Arc::new(Mutex::new(GeneratedFiles::default())),
+            common_rules: None,
+            review: Arc::new(Mutex::new(Vec::new())),
+            review_state: RefCell::new(ReviewState::default()),
+            logs_state: RefCell::new(LogsState::default()),
+        }
+    }
+
+    /// Decides on the selected file, then moves on to the next one that is still open
+    fn decide_selected(&self, decision: Decision) {
+        let mut files = self.review.lock().unwrap();
+        let mut state = self.review_state.borrow_mut();
+        let Some(selected) = state.selected() else {
+            return;
+        };
+        if let Some(file) = files.get_mut(selected) {
+            decide(&self.generated, file, decision);
+        }
+
+        let next_open = (selected + 1..files.len())
+            .chain(0..selected)
+            .find(|index| files[*index].decision.is_open());
+        if let Some(next) = next_open {
+            state.select(next);
+        }
+    }
+}
+
+/// Writes a generated file, creating its directory if needed, and records its hash
+fn write_file(
+    generated: &Mutex<GeneratedFiles>,
+    plugin_id: &str,
+    file_name: &str,
+    path: &Path,
+    contents: &str,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    events::emit(Event::FileWritten {
+        plugin: Some(plugin_id),
+        path,
+    });
+    record(generated, plugin_id, file_name, contents)
+}
+
+/// Marks a file as owned by flint, with the contents it was generated with
+fn record(
+    generated: &Mutex<GeneratedFiles>,
+    plugin_id: &str,
+    file_name: &str,
+    contents: &str,
+) -> Result<()> {
+    let mut generated = generated.lock().unwrap();
+    generated.record(plugin_id, file_name, contents);
+    generated.save(&GeneratedFiles::path(&get_flag!(current_dir)))
+}
+
+/// Applies a decision to a file that hasn't been decided on yet, writing it if accepted
+fn decide(generated: &Mutex<GeneratedFiles>, file: &mut ReviewFile, decision: Decision) {
+    if !file.decision.is_open() {
+        return;
+    }
+    match decision {
+        Decision::Accepted => {
+            let written = write_file(
+                generated,
+                &file.plugin,
+                &file.name,
+                &file.path,
+                &file.generated,
+            );
+            if let Err(err) = written {
+                error!("Failed to write {}: {}", file.path.display(), err);
+                return;
+            }
+            success!("Wrote {}", file.name);
         }
+        Decision::KeptOurs => info!("Kept the current {}", file.name),
+        _ => (),
     }
+    file.decision = decision;
+}
+
+/// A unified diff between a file's current contents (if it exists) and the generated ones
+fn file_diff(file_name: &str, current: Option<&str>, generated: &str) -> String {
+    let old_header = match current {
+        Some(_) => format!("a/{}", file_name),
+        None => "/dev/null".to_string(),
+    };
+    TextDiff::from_lines(current.unwrap_or_default(), generated)
+        .unified_diff()
+        .header(&old_header, &format!("b/{}", file_name))
+        .to_string()
 }
 
 impl AppWidget for GenerateWidget {
     fn setup(&mut self) -> Result<()> {
         let config_path = get_flag!(config_path);
-        let toml = Arc::new(Config::load(&config_path).unwrap());
+        let toml = Arc::new(Config::load(&config_path)?);
+        *self.generated.lock().unwrap() =
+            GeneratedFiles::load(&GeneratedFiles::path(&get_flag!(current_dir)))?;
         let mut plugin_ids = Vec::new();
         plugin_ids.extend(toml.rules.keys());
         plugin_ids.extend(toml.tests.keys());
         plugin_ids.extend(toml.ci.keys());
 
-        self.plugins = plugin::list()
-            .unwrap()
-            .into_iter()
+        self.plugins = plugin::list()?
+            .iter()
             .filter(|plugin| plugin_ids.contains(&&plugin.details.id))
             .cloned()
             .collect();
 
+        let common_rules = CommonRulesMatrix::new(&toml, &self.plugins);
+        if !common_rules.is_empty() {
+            info!(
+                "What the linters do with [rules.common]:\n{}",
+                common_rules.render()
+            );
+            self.common_rules = Some(common_rules);
+        }
+
         for plugin in &self.plugins {
             let plugin = plugin.clone();
             let toml_clone = toml.clone();
             let pool = self.thread_pool.as_ref().unwrap();
+            let failed = self.failed.clone();
+            let outdated = self.outdated.clone();
+            let drifted = self.drifted.clone();
+            let generated = self.generated.clone();
+            let review = self.review.clone();
+            let args = self.args.clone();
 
             pool.execute(move || {
+                let id = &plugin.details.id;
+                events::emit(Event::PluginStarted {
+                    plugin: id,
+                    kind: &plugin.kind,
+                });
+                let fail = |message: String| {
+                    failed.store(true, Ordering::SeqCst);
+                    events::emit(Event::Error {
+                        plugin: Some(id),
+                        message: &message,
+                    });
+                    events::emit(Event::PluginFinished {
+                        plugin: id,
+                        kind: &plugin.kind,
+                        status: "failed",
+                        error: Some(&message),
+                    });
+                };
+
                 let result = plugin.generate(&toml_clone);
                 info!("Generating {} config", plugin.details.id);
                 match result {
                     Ok(res) => {
-                        // TODO: Ask user if we want to overwrite files
                         let flint_path = get_flag!(current_dir);
-                        for (file_name, contents) in res {
-                            fs::create_dir_all(&flint_path).unwrap();
-                            std::fs::write(flint_path.join(file_name), contents).unwrap();
+                        let mut files = res.into_iter().collect::<Vec<_>>();
+                        files.sort();
+                        for (file_name, contents) in files {
+                            let path = flint_path.join(&file_name);
+                            let current = fs::read_to_string(&path).ok();
+                            let is_drifted = generated.lock().unwrap().drifted(
+                                &file_name,
+                                current.as_deref(),
+                                &contents,
+                            );
+                            let up_to_date = current.as_deref() == Some(contents.as_str());
+
+                            if args.writes_nothing() {
+                                if up_to_date {
+                                    info!("{} is up to date", file_name);
+                                    continue;
+                                }
+                                if is_drifted {
+                                    warn!("{} was edited since it was generated", file_name);
+                                }
+                                let diff = file_diff(&file_name, current.as_deref(), &contents);
+                                if args.dry_run {
+                                    info!("Would write {}:\n{}", file_name, diff.trim_end());
+                                } else {
+                                    warn!("{} is out of date", file_name);
+                                }
+                                events::emit(Event::FileOutdated {
+                                    plugin: Some(id),
+                                    path: &path,
+                                    diff: &diff,
+                                });
+                                outdated.lock().unwrap().push(path);
+                                continue;
+                            }
+
+                            // The TUI asks before writing anything
+                            if !get_flag!(non_interactive) {
+                                // Nothing to ask about, but flint owns the file from now on
+                                if up_to_date {
+                                    if let Err(err) = record(&generated, id, &file_name, &contents)
+                                    {
+                                        error!("Failed to record {}: {}", file_name, err);
+                                    }
+                                }
+                                review.lock().unwrap().push(ReviewFile::new(
+                                    id, &file_name, path, contents, is_drifted,
+                                ));
+                                continue;
+                            }
+
+                            if is_drifted && !args.force {
+                                warn!(
+                                    "{} was edited since it was generated, leaving it alone",
+                                    file_name
+                                );
+                                events::emit(Event::FileDrifted {
+                                    plugin: Some(id),
+                                    path: &path,
+                                });
+                                drifted.lock().unwrap().push(path);
+                                continue;
+                            }
+
+                            if let Err(err) =
+                                write_file(&generated, id, &file_name, &path, &contents)
+                            {
+                                error!("Failed to write {}: {}", path.display(), err);
+                                fail(format!("failed to write {}: {}", path.display(), err));
+                                return;
+                            }
+                        }
+                        events::emit(Event::PluginFinished {
+                            plugin: id,
+                            kind: &plugin.kind,
+                            status: if args.writes_nothing() {
+                                "checked"
+                            } else {
+                                "generated"
+                            },
+                            error: None,
+                        });
+                        if args.writes_nothing() {
+                            // Reported once every plugin is done
+                        } else if get_flag!(non_interactive) {
+                            success!("Generated {} config successfully", plugin.details.id)
+                        } else {
+                            success!(
+                                "Generated {} config, review its files above",
+                                plugin.details.id
+                            )
                         }
-                        success!("Generated {} config successfully", plugin.details.id)
                     }
                     Err(err) => {
                         error!(
                             "Error while generating {} config: {}",
                             plugin.details.id, err
                         );
+                        fail(err.to_string());
                     }
                 }
             });
@@ -88,12 +355,153 @@ impl AppWidget for GenerateWidget {
     fn set_thread_pool(&mut self, thread_pool: &ThreadPool) {
         self.thread_pool = Some(thread_pool.clone())
     }
+
+    fn handle_events(&mut self, event: crossterm::event::Event) -> Result<()> {
+        let _ = handle_key_events(event.clone(), |_, key_code| {
+            match key_code {
+                KeyCode::Up => self.review_state.borrow_mut().select_previous(),
+                KeyCode::Down => {
+                    let len = self.review.lock().unwrap().len();
+                    self.review_state.borrow_mut().select_next(len);
+                }
+                KeyCode::PageUp => self.review_state.borrow_mut().scroll_up(10),
+                KeyCode::PageDown => self.review_state.borrow_mut().scroll_down(10),
+                KeyCode::Char('a') => self.decide_selected(Decision::Accepted),
+                KeyCode::Char('o') => self.decide_selected(Decision::KeptOurs),
+                KeyCode::Char('s') => self.decide_selected(Decision::Skipped),
+                KeyCode::Char('A') => {
+                    // Like non-interactive runs, leave hand-edited files alone unless forced,
+                    // they have to be accepted one by one
+                    let mut left_drifted = 0;
+                    for file in self.review.lock().unwrap().iter_mut() {
+                        if file.drifted && !self.args.force {
+                            left_drifted += usize::from(file.decision.is_open());
+                            continue;
+                        }
+                        decide(&self.generated, file, Decision::Accepted);
+                    }
+                    if left_drifted > 0 {
+                        warn!(
+                            "Left {} files that were edited since they were generated, accept them one by one",
+                            left_drifted
+                        );
+                    }
+                }
+                _ => (),
+            }
+            Ok(())
+        });
+
+        handle_mouse_event(event.clone(), |mouse_event| match mouse_event {
+            MouseEventKind::ScrollUp => {
+                self.review_state.borrow_mut().scroll_up(1);
+                Ok(())
+            }
+            MouseEventKind::ScrollDown => {
+                self.review_state.borrow_mut().scroll_down(1);
+                Ok(())
+            }
+            _ => Ok(()),
+        })
+    }
+
+    fn finish(&mut self) {
+        let outdated = self.outdated.lock().unwrap();
+        let failed = self.failed.load(Ordering::SeqCst);
+        if self.args.writes_nothing() && !failed {
+            if outdated.is_empty() {
+                success!("All generated files are up to date");
+            } else if self.args.check {
+                error!(
+                    "{} generated files are out of date, run `flint generate` to update them",
+                    outdated.len()
+                );
+            } else {
+                info!("{} files would change", outdated.len());
+            }
+        }
+
+        let drifted = self.drifted.lock().unwrap();
+        if !drifted.is_empty() {
+            warn!(
+                "{} files were edited since they were generated and weren't overwritten, use `flint generate --force` to overwrite them",
+                drifted.len()
+            );
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        let outdated = self.outdated.lock().unwrap();
+        let failed = self.failed.load(Ordering::SeqCst);
+        if failed || (self.args.check && !outdated.is_empty()) {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn summary(&self) -> Map<String, Value> {
+        let mut summary = Map::new();
+        if self.args.writes_nothing() {
+            let outdated = self.outdated.lock().unwrap();
+            summary.insert("outdated".into(), json!(*outdated));
+        } else {
+            let drifted = self.drifted.lock().unwrap();
+            summary.insert("drifted".into(), json!(*drifted));
+        }
+        if let Some(common_rules) = &self.common_rules {
+            summary.insert("common_rules".into(), common_rules.to_json());
+        }
+        summary
+    }
 }
 
+const LOGS_HEIGHT: u16 = 8;
+
 impl WidgetRef for GenerateWidget {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let files = self.review.lock().unwrap();
+        let review = ReviewWidget { files: &files };
+        let mut review_state = self.review_state.borrow_mut();
+
+        // Follow the latest logs, they're only there to show how generating went
+        let mut logs_state = self.logs_state.borrow_mut();
+        let log_lines = get_logs()
+            .map(|logs| logs.iter().map(|(_, log)| log.lines().count()).sum())
+            .unwrap_or(0);
+        logs_state.scroll_to_bottom(log_lines, LOGS_HEIGHT.saturating_sub(2) as usize);
+
+        let key = |key: &'static str| Span::styled(key, Style::default().fg(Color::Yellow).bold());
+        let help = Line::from(vec![
+            key(" ↑/↓"),
+            Span::raw(" select file  "),
+            key("PgUp/PgDn"),
+            Span::raw(" scroll diff  "),
+            key("a"),
+            Span::raw(" accept  "),
+            key("o"),
+            Span::raw(" keep ours  "),
+            key("s"),
+            Span::raw(" skip  "),
+            key("A"),
+            Span::raw(" accept all  "),
+            key("Esc"),
+            Span::raw(" exit"),
+        ]);
+
         ui!((area, buf) => {
-            { self.logs_widget }
+            Layout(
+                direction: Direction::Vertical,
+                constraints: [Constraint::Fill(1), Constraint::Length(1), Constraint::Length(LOGS_HEIGHT)]
+            ) {
+                Stateful(&mut review_state) {
+                    { review }
+                },
+                Paragraph::new(help),
+                Stateful(&mut logs_state) {
+                    { self.logs_widget }
+                }
+            }
         });
     }
 }
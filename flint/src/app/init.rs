@@ -1,9 +1,9 @@
 use super::AppWidget;
-use crate::util::{handle_key_events, lang::Language, toml::Config};
+use crate::util::{events, handle_key_events, lang::Language, toml::Config};
 use clap::Parser;
 use crossterm::event::{Event, KeyCode};
 use flint_macros::{ui, widget as w};
-use flint_utils::{get_flag, info, Error, Result};
+use flint_utils::{get_flag, info, success, warn, Error, Result};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     prelude::*,
@@ -27,6 +27,10 @@ pub struct InitWidgetArgs {
     /// Show help for the init command
     #[clap(short, long)]
     help: bool,
+
+    /// Create flint.toml without asking, overwriting an existing one
+    #[clap(short, long)]
+    pub yes: bool,
 }
 
 impl<'a> InitWidget<'a> {
@@ -113,6 +117,25 @@ impl<'a> AppWidget for InitWidget<'a> {
             self.config_exists = true;
         }
 
+        if self.args.yes {
+            for lang in &self.langs {
+                match lang {
+                    Language::Supported(name) => info!("Found {}", name),
+                    Language::Unsupported(name) => warn!("Found {}, which is not supported", name),
+                }
+            }
+            if self.config_exists {
+                warn!("Overwriting {}", config_path.display());
+            }
+            Config::create_default(config_path.clone())?;
+            self.created_config = true;
+            events::emit(events::Event::FileWritten {
+                plugin: None,
+                path: &config_path,
+            });
+            success!("Created {}", config_path.display());
+        }
+
         Ok(())
     }
 
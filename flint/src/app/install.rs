@@ -1,8 +1,11 @@
 use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::plugin::download::download_plugins_from_config;
+use crate::plugin::download::{download_plugins_from_config, Pins};
+use crate::util::events::{self, Event};
 use crate::util::toml::Config;
 use crate::util::{handle_key_events, handle_mouse_event};
 use crate::widgets::logs::{LogsState, LogsWidget};
@@ -25,6 +28,8 @@ pub struct InstallWidget {
     pool: Option<ThreadPool>,
     exit_sender: Option<Sender<()>>,
     logs_state: RefCell<LogsState>,
+    pins: Pins,
+    failed: Arc<AtomicBool>,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -52,6 +57,20 @@ pub struct InstallArgs {
     /// Show logs
     #[clap(long)]
     logs: bool,
+
+    /// Install only from the plugin cache, without touching the network
+    #[clap(long)]
+    offline: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct UpdateArgs {
+    /// Plugins to move to the latest commit of plugins_branch (all plugins if none are given)
+    plugins: Vec<String>,
+
+    /// Show help for the update command
+    #[clap(short, long)]
+    help: bool,
 }
 
 impl InstallWidget {
@@ -62,6 +81,24 @@ impl InstallWidget {
             exit_sender: None,
             logs_state: RefCell::new(LogsState::default()),
             args,
+            pins: Pins::Locked,
+            failed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Installs plugins like `flint install`, but moves the given plugins' pins in flint.lock forward
+    pub fn update(args: UpdateArgs) -> Self {
+        Self {
+            pins: Pins::Update(args.plugins),
+            ..Self::new(InstallArgs {
+                plugins: Vec::new(),
+                lint: false,
+                test: false,
+                all: true,
+                help: args.help,
+                logs: false,
+                offline: false,
+            })
         }
     }
 }
@@ -75,19 +112,37 @@ impl AppWidget for InstallWidget {
         };
 
         let config_path = get_flag!(config_path);
-        let toml = Config::load(&config_path).unwrap();
+        let toml = Config::load(&config_path)?;
         let toml_clone = toml.clone();
+        let pins = self.pins.clone();
+        let offline = self.args.offline;
+        let failed = self.failed.clone();
         let pool = self.pool.as_ref().unwrap();
         pool.execute(move || {
             std::thread::sleep(Duration::from_secs(10));
-            match download_plugins_from_config(&toml_clone) {
+            match download_plugins_from_config(&toml_clone, &pins, offline) {
                 Ok(_) => success!("Plugins downloaded successfully"),
-                Err(e) => error!("Error downloading plugins: {}", e),
+                Err(e) => {
+                    failed.store(true, Ordering::SeqCst);
+                    error!("Error downloading plugins: {}", e);
+                    events::emit(Event::Error {
+                        plugin: None,
+                        message: &e.to_string(),
+                    });
+                }
             }
         });
         Ok(())
     }
 
+    fn exit_code(&self) -> i32 {
+        if self.failed.load(Ordering::SeqCst) {
+            1
+        } else {
+            0
+        }
+    }
+
     fn set_exit_sender(&mut self, exit_sender: Sender<()>) {
         self.exit_sender = Some(exit_sender);
     }
@@ -1,15 +1,19 @@
 pub use app::*;
 
 pub mod app;
+pub mod check_config;
 pub mod generate;
 pub mod help;
 pub mod init;
 pub mod install;
+pub mod plugin;
+pub mod status;
 pub mod test;
 
 use crossterm::event::Event;
 use flint_utils::Result;
 use ratatui::widgets::WidgetRef;
+use serde_json::{Map, Value};
 use std::sync::mpsc::Sender;
 use threadpool::ThreadPool;
 
@@ -23,4 +27,20 @@ pub trait AppWidget: WidgetRef {
     fn set_exit_sender(&mut self, _exit_sender: Sender<()>) {}
 
     fn set_thread_pool(&mut self, _thread_pool: &ThreadPool) {}
+
+    /// Runs once in non-interactive runs, after all the work queued on the thread
+    /// pool has finished. Writes whatever needs the results of all of it.
+    fn finish(&mut self) {}
+
+    /// Exit code for non-interactive runs. Only called after `finish`, and shouldn't
+    /// have side effects.
+    fn exit_code(&self) -> i32 {
+        0
+    }
+
+    /// Fields for the summary printed at the end with `--output json`, next to the
+    /// command and exit code. Called after `exit_code`.
+    fn summary(&self) -> Map<String, Value> {
+        Map::new()
+    }
 }
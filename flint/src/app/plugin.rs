@@ -0,0 +1,234 @@
+use std::{cell::RefCell, path::PathBuf};
+
+use clap::{Parser, Subcommand};
+use crossterm::event::{KeyCode, MouseEventKind};
+use flint_macros::ui;
+use flint_utils::{error, info, success, warn, Result};
+use ratatui::prelude::*;
+use ratatui::widgets::WidgetRef;
+
+use super::AppWidget;
+use crate::{
+    plugin::{
+        harness::{self, Outcome},
+        scaffold::scaffold,
+        PluginKind,
+    },
+    util::{handle_key_events, handle_mouse_event},
+    widgets::logs::{LogsState, LogsWidget},
+};
+
+#[derive(Debug)]
+pub struct PluginWidget {
+    logs: LogsWidget,
+    logs_state: RefCell<LogsState>,
+    failed: bool,
+    args: PluginArgs,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct PluginArgs {
+    #[command(subcommand)]
+    command: PluginCommand,
+
+    /// Show help for the plugin command
+    #[clap(short, long)]
+    help: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+#[command(disable_help_flag = true)]
+pub enum PluginCommand {
+    /// Creates a new plugin from a template
+    New(PluginNewArgs),
+    /// Checks a plugin's Run, Eval and Generate output against its recorded fixtures
+    Test(PluginTestArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct PluginNewArgs {
+    /// Kind of plugin to create
+    kind: PluginKind,
+
+    /// Id of the plugin, e.g. my-linter
+    id: String,
+
+    /// Directory to create the plugin in (defaults to ./<id>)
+    #[clap(long)]
+    dir: Option<PathBuf>,
+
+    /// Show help for the plugin new command
+    #[clap(short, long)]
+    help: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct PluginTestArgs {
+    /// Directory of the plugin to test
+    #[clap(default_value = ".")]
+    path: PathBuf,
+
+    /// Kind of the plugin, if it can't be told from where it is installed or its fixtures
+    #[clap(long)]
+    kind: Option<PluginKind>,
+
+    /// Record the plugin's current output as the expected output
+    #[clap(long)]
+    update: bool,
+
+    /// Show help for the plugin test command
+    #[clap(short, long)]
+    help: bool,
+}
+
+impl PluginWidget {
+    pub fn new(args: PluginArgs) -> Self {
+        Self {
+            logs: LogsWidget,
+            logs_state: RefCell::new(LogsState::default()),
+            failed: false,
+            args,
+        }
+    }
+
+    fn new_plugin(&self, args: &PluginNewArgs) -> Result<()> {
+        let dest = match &args.dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir()?.join(&args.id),
+        };
+
+        let files = scaffold(&args.kind, &args.id, &dest)?;
+        for file in &files {
+            info!("Created {}", file.display());
+        }
+
+        success!(
+            "Created {} plugin {} in {}",
+            args.kind.to_string(),
+            args.id,
+            dest.display()
+        );
+        info!(
+            "Try it out with: cd {} && flint {}",
+            dest.join("example").display(),
+            match args.kind {
+                PluginKind::Ci => "generate",
+                _ => "test",
+            }
+        );
+        info!(
+            "Check it against its fixtures with: cd {} && flint plugin test",
+            dest.display()
+        );
+        Ok(())
+    }
+
+    fn test_plugin(&mut self, args: &PluginTestArgs) -> Result<()> {
+        let plugin = harness::load(&args.path, args.kind.clone())?;
+        let fixtures = harness::fixtures(&plugin)?;
+        if fixtures.is_empty() {
+            warn!(
+                "{} has no fixtures, add some to {}",
+                plugin.details.id,
+                plugin.path.join(harness::FIXTURES_DIR).display()
+            );
+            return Ok(());
+        }
+
+        let (mut passed, mut updated, mut failed) = (0, 0, 0);
+        for fixture in fixtures {
+            for result in harness::run_fixture(&plugin, &fixture, args.update)? {
+                match result.outcome {
+                    Outcome::Passed => {
+                        passed += 1;
+                        success!("{}: {} ok", result.fixture, result.check.name());
+                    }
+                    Outcome::Updated => {
+                        updated += 1;
+                        info!("{}: {} updated", result.fixture, result.check.name());
+                    }
+                    Outcome::Failed(details) => {
+                        failed += 1;
+                        error!(
+                            "{}: {} failed\n{}",
+                            result.fixture,
+                            result.check.name(),
+                            details
+                        );
+                    }
+                }
+            }
+        }
+
+        if args.update {
+            success!("Updated {} snapshots of {}", updated, plugin.details.id);
+        } else if failed > 0 {
+            self.failed = true;
+            error!("{} passed, {} failed", passed, failed);
+        } else {
+            success!("{} passed", passed);
+        }
+        Ok(())
+    }
+}
+
+impl AppWidget for PluginWidget {
+    fn setup(&mut self) -> Result<()> {
+        let result = match self.args.command.clone() {
+            PluginCommand::New(args) => self.new_plugin(&args),
+            PluginCommand::Test(args) => self.test_plugin(&args),
+        };
+
+        if let Err(err) = result {
+            self.failed = true;
+            error!("{}", err);
+        }
+
+        Ok(())
+    }
+
+    fn handle_events(&mut self, event: crossterm::event::Event) -> Result<()> {
+        let _ = handle_key_events(event.clone(), |_, key_code| match key_code {
+            KeyCode::Up => {
+                self.logs_state.borrow_mut().scroll_up(1);
+                Ok(())
+            }
+            KeyCode::Down => {
+                self.logs_state.borrow_mut().scroll_down(1);
+                Ok(())
+            }
+            _ => Ok(()),
+        });
+
+        handle_mouse_event(event.clone(), |mouse_event| match mouse_event {
+            MouseEventKind::ScrollUp => {
+                self.logs_state.borrow_mut().scroll_up(1);
+                Ok(())
+            }
+            MouseEventKind::ScrollDown => {
+                self.logs_state.borrow_mut().scroll_down(1);
+                Ok(())
+            }
+            _ => Ok(()),
+        })
+    }
+
+    fn exit_code(&self) -> i32 {
+        if self.failed {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl WidgetRef for PluginWidget {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let mut logs_state = self.logs_state.borrow_mut();
+        ui!((area, buf) => {
+            Stateful(&mut logs_state) {
+                { self.logs }
+            }
+        });
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    fs,
+    sync::Arc,
+};
+
+use clap::Parser;
+use crossterm::event::{KeyCode, MouseEventKind};
+use flint_macros::ui;
+use flint_utils::{error, get_flag, info, success, warn, Result};
+use ratatui::prelude::*;
+use ratatui::widgets::WidgetRef;
+use serde_json::{json, Map, Value};
+
+use super::AppWidget;
+use crate::{
+    plugin::{
+        self,
+        generated::{FileStatus, GeneratedFiles},
+        PluginKind,
+    },
+    util::{handle_key_events, handle_mouse_event, toml::Config},
+    widgets::logs::{LogsState, LogsWidget},
+};
+
+#[derive(Debug)]
+pub struct StatusWidget {
+    logs: LogsWidget,
+    logs_state: RefCell<LogsState>,
+    /// Status of each generated file, with the plugin that generates it
+    files: BTreeMap<String, (String, FileStatus)>,
+    failed: usize,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct StatusArgs {
+    /// Show help for the status command
+    #[clap(short, long)]
+    help: bool,
+}
+
+impl StatusWidget {
+    pub fn new(_args: StatusArgs) -> Self {
+        Self {
+            logs: LogsWidget,
+            logs_state: RefCell::new(LogsState::default()),
+            files: BTreeMap::new(),
+            failed: 0,
+        }
+    }
+
+    fn count(&self, status: FileStatus) -> usize {
+        self.files
+            .values()
+            .filter(|(_, other)| *other == status)
+            .count()
+    }
+}
+
+impl AppWidget for StatusWidget {
+    fn setup(&mut self) -> Result<()> {
+        let project_dir = get_flag!(current_dir);
+        let toml = Arc::new(Config::load(&get_flag!(config_path))?);
+        let generated = GeneratedFiles::load(&GeneratedFiles::path(&project_dir))?;
+        let mut failed_plugins = HashSet::new();
+
+        for plugin in plugin::list_from_config(&toml)? {
+            // Report plugins write their files during `flint test`, they don't generate any
+            if plugin.kind == PluginKind::Report {
+                continue;
+            }
+            let id = &plugin.details.id;
+            match plugin.generate(&toml) {
+                Ok(output) => {
+                    for (file_name, contents) in output {
+                        let current = fs::read_to_string(project_dir.join(&file_name)).ok();
+                        let status = generated.status(&file_name, current.as_deref(), &contents);
+                        self.files.insert(file_name, (id.clone(), status));
+                    }
+                }
+                Err(err) => {
+                    error!("Error while generating {} config: {}", id, err);
+                    failed_plugins.insert(id.clone());
+                    self.failed += 1;
+                }
+            }
+        }
+
+        // Files flint generated before that no configured plugin generates now. Files
+        // of plugins that just failed can't be told apart, so they're left out.
+        let orphaned = generated.orphaned(&project_dir, |file_name, plugin| {
+            self.files.contains_key(file_name) || failed_plugins.contains(plugin)
+        });
+        for (file_name, plugin) in orphaned {
+            self.files.insert(file_name, (plugin, FileStatus::Orphaned));
+        }
+
+        for (file_name, (plugin_id, status)) in &self.files {
+            let line = format!("{:<9} {} ({})", status.as_str(), file_name, plugin_id);
+            match status {
+                FileStatus::Generated => success!("{}", line),
+                _ => warn!("{}", line),
+            }
+        }
+
+        info!(
+            "{} generated, {} drifted, {} stale, {} orphaned",
+            self.count(FileStatus::Generated),
+            self.count(FileStatus::Drifted),
+            self.count(FileStatus::Stale),
+            self.count(FileStatus::Orphaned),
+        );
+        if self.count(FileStatus::Drifted) > 0 {
+            info!("Drifted files were edited by hand, `flint generate --force` overwrites them");
+        }
+        if self.count(FileStatus::Stale) > 0 {
+            info!("Run `flint generate` to update stale files");
+        }
+        if self.count(FileStatus::Orphaned) > 0 {
+            info!("Orphaned files aren't generated by any configured plugin anymore");
+        }
+
+        Ok(())
+    }
+
+    fn handle_events(&mut self, event: crossterm::event::Event) -> Result<()> {
+        let _ = handle_key_events(event.clone(), |_, key_code| match key_code {
+            KeyCode::Up => {
+                self.logs_state.borrow_mut().scroll_up(1);
+                Ok(())
+            }
+            KeyCode::Down => {
+                self.logs_state.borrow_mut().scroll_down(1);
+                Ok(())
+            }
+            _ => Ok(()),
+        });
+
+        handle_mouse_event(event.clone(), |mouse_event| match mouse_event {
+            MouseEventKind::ScrollUp => {
+                self.logs_state.borrow_mut().scroll_up(1);
+                Ok(())
+            }
+            MouseEventKind::ScrollDown => {
+                self.logs_state.borrow_mut().scroll_down(1);
+                Ok(())
+            }
+            _ => Ok(()),
+        })
+    }
+
+    fn exit_code(&self) -> i32 {
+        if self.failed > 0 {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn summary(&self) -> Map<String, Value> {
+        let files = self
+            .files
+            .iter()
+            .map(|(file_name, (plugin_id, status))| {
+                json!({ "path": file_name, "plugin": plugin_id, "status": status })
+            })
+            .collect::<Vec<_>>();
+        let mut summary = Map::new();
+        summary.insert("files".into(), files.into());
+        summary
+    }
+}
+
+impl WidgetRef for StatusWidget {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let mut logs_state = self.logs_state.borrow_mut();
+        ui!((area, buf) => {
+            Stateful(&mut logs_state) {
+                { self.logs }
+            }
+        });
+    }
+}
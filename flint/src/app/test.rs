@@ -3,16 +3,38 @@ use crossterm::event::{KeyCode, MouseEventKind};
 use flint_macros::ui;
 use ratatui::prelude::*;
 use ratatui::widgets::WidgetRef;
-use std::{cell::RefCell, fs, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 use threadpool::ThreadPool;
 
 use crate::{
-    plugin::{self, Plugin, PluginKind},
-    util::{handle_key_events, handle_mouse_event, toml::Config},
+    plugin::{
+        self,
+        baseline::{Baseline, BaselineEntry, BASELINE_FILE},
+        exec::eval::PluginEvalOutput,
+        format::{PluginResults, ResultFormat},
+        process::{self, PlanResult, RunLimits},
+        result_cache::{self, ResultCache},
+        schedule::{self, Job, ResourceGuard, Resources},
+        suppress,
+        verdict::{PluginOutcome, Verdict, EXIT_PLUGIN_ERROR},
+        Plugin, PluginKind,
+    },
+    util::{
+        events::{self, Event},
+        git, handle_key_events, handle_mouse_event,
+        toml::{Config, ThresholdsConfig},
+    },
     widgets::logs::{LogsState, LogsWidget},
 };
 
-use flint_utils::{error, get_flag, info, success, Result};
+use flint_utils::{error, get_flag, info, success, warn, Error, Result};
+use serde_json::{json, Map, Value};
 
 use super::AppWidget;
 
@@ -21,7 +43,19 @@ pub struct TestWidget {
     logs: LogsWidget,
     thread_pool: Option<ThreadPool>,
     logs_state: RefCell<LogsState>,
+    outcomes: Arc<Mutex<Vec<(String, PluginOutcome)>>>,
+    thresholds: ThresholdsConfig,
     args: TestArgs,
+    /// Save the lint results as the baseline instead of checking them
+    write_baseline: bool,
+    /// Whether some plugin's results couldn't be saved to the baseline
+    baseline_failed: bool,
+    /// Baseline entries that were fixed
+    fixed: Arc<Mutex<Vec<BaselineEntry>>>,
+    /// Unused and malformed suppression comments
+    suppression_warnings: Arc<Mutex<BTreeSet<String>>>,
+    /// The lint and test plugins being run
+    plugins: Vec<Plugin>,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -38,6 +72,58 @@ pub struct TestArgs {
 
     #[clap(short, long)]
     test: bool,
+
+    /// Maximum number of plugins to run at once (default: number of CPUs)
+    #[clap(short, long)]
+    jobs: Option<usize>,
+
+    /// Only check files that changed, and only run the plugins that handle them
+    #[clap(long)]
+    changed: bool,
+
+    /// With --changed, the branch, tag or commit to compare against (default: uncommitted changes only)
+    #[clap(long, requires = "changed")]
+    since: Option<String>,
+
+    /// Run every plugin, even if its cached result is still valid
+    #[clap(long)]
+    no_cache: bool,
+
+    /// Also write the results in this format, e.g. for code scanning dashboards
+    #[clap(long, value_enum)]
+    format: Option<ResultFormat>,
+
+    /// Where to write the results with --format (default: flint-results.<extension>)
+    #[clap(long, requires = "format")]
+    output_file: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct BaselineArgs {
+    /// Show help for the baseline command
+    #[clap(short, long)]
+    help: bool,
+
+    /// Maximum number of plugins to run at once (default: number of CPUs)
+    #[clap(short, long)]
+    jobs: Option<usize>,
+}
+
+/// What the plugins of a run share
+struct TestRun {
+    toml: Arc<Config>,
+    report_plugins: Vec<Plugin>,
+    resources: Arc<Resources>,
+    /// The files each plugin should check, with --changed
+    changed_files: Option<HashMap<(PluginKind, String), Vec<String>>>,
+    cache: ResultCache,
+    /// Whether cached results may be used, they are saved either way
+    use_cache: bool,
+    /// Known issues to leave out of the results
+    baseline: Option<Baseline>,
+    write_baseline: bool,
+    fixed: Arc<Mutex<Vec<BaselineEntry>>>,
+    suppression_warnings: Arc<Mutex<BTreeSet<String>>>,
 }
 
 impl TestWidget {
@@ -46,8 +132,128 @@ impl TestWidget {
             thread_pool: None,
             logs: LogsWidget::default(),
             logs_state: RefCell::new(LogsState::default()),
+            outcomes: Arc::new(Mutex::new(Vec::new())),
+            thresholds: ThresholdsConfig::default(),
             args,
+            write_baseline: false,
+            baseline_failed: false,
+            fixed: Arc::new(Mutex::new(Vec::new())),
+            suppression_warnings: Arc::new(Mutex::new(BTreeSet::new())),
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Runs the lint plugins like `flint test --lint`, and saves their results as the baseline
+    pub fn baseline(args: BaselineArgs) -> Self {
+        Self {
+            write_baseline: true,
+            ..Self::new(TestArgs {
+                help: args.help,
+                all: false,
+                lint: true,
+                test: false,
+                jobs: args.jobs,
+                changed: false,
+                since: None,
+                no_cache: false,
+                format: None,
+                output_file: None,
+            })
+        }
+    }
+
+    /// Writes the results for `--format`
+    fn write_results(
+        &self,
+        format: ResultFormat,
+        outcomes: &[(String, PluginOutcome)],
+    ) -> Result<()> {
+        let errors = outcomes
+            .iter()
+            .map(|(_, outcome)| outcome.error())
+            .collect::<Vec<_>>();
+
+        let results = outcomes
+            .iter()
+            .zip(&errors)
+            // Skipped plugins have nothing to say
+            .filter(|((_, outcome), _)| !matches!(outcome, PluginOutcome::Skipped(_)))
+            .map(|((plugin_id, outcome), error)| PluginResults {
+                plugin_id,
+                version: self
+                    .plugins
+                    .iter()
+                    .find(|plugin| &plugin.details.id == plugin_id)
+                    .map(|plugin| plugin.details.version.as_str()),
+                output: outcome.output(),
+                error: error.as_deref(),
+            })
+            .collect::<Vec<_>>();
+
+        let path = match &self.args.output_file {
+            Some(path) => path.clone(),
+            None => get_flag!(current_dir).join(format.default_file()),
+        };
+        fs::write(&path, format.render(&results)?)?;
+        events::emit(Event::FileWritten {
+            plugin: None,
+            path: &path,
+        });
+        success!("Wrote the results to {}", path.display());
+        Ok(())
+    }
+
+    /// Saves the lint results as the baseline, returns false if some of them couldn't be
+    fn save_baseline(&self) -> bool {
+        let outcomes = self.outcomes.lock().unwrap();
+        let path = Baseline::path(&get_flag!(current_dir));
+        let previous = match Baseline::load(&path) {
+            Ok(previous) => previous.unwrap_or_default(),
+            Err(err) => {
+                warn!("Ignoring the existing {}: {}", BASELINE_FILE, err);
+                Baseline::default()
+            }
+        };
+
+        let mut baseline = Baseline::default();
+        let mut failed = false;
+        for (plugin_id, outcome) in outcomes.iter() {
+            match outcome {
+                PluginOutcome::Evaluated(PluginEvalOutput::Lint(output)) => {
+                    baseline.add(plugin_id, output)
+                }
+                // Don't lose the known issues of a plugin just because it couldn't run this time
+                _ => {
+                    error!(
+                        "{} has no results, keeping its entries from the previous baseline",
+                        plugin_id
+                    );
+                    baseline
+                        .issues
+                        .extend(previous.for_plugin(plugin_id).cloned());
+                    failed = true;
+                }
+            }
         }
+
+        if let Err(err) = baseline.save(&path) {
+            error!("Failed to write {}: {}", path.display(), err);
+            return false;
+        }
+        events::emit(Event::FileWritten {
+            plugin: None,
+            path: &path,
+        });
+
+        let total = baseline.issues.iter().map(|entry| entry.count).sum::<u32>();
+        success!(
+            "Saved {} issues from {} plugins to {}",
+            total,
+            outcomes.len(),
+            BASELINE_FILE
+        );
+
+        !failed
     }
 }
 
@@ -55,7 +261,7 @@ impl AppWidget for TestWidget {
     fn setup(&mut self) -> Result<()> {
         let config_path = get_flag!(config_path);
         flint_utils::debug!("Config path: {:#?}", &config_path);
-        let toml = Arc::new(Config::load(&config_path).unwrap());
+        let toml = Arc::new(Config::load(&config_path)?);
         if let Some(ref env) = toml.flint.env {
             let cwd = get_flag!(current_dir);
             let env_path = cwd.join(env);
@@ -63,9 +269,9 @@ impl AppWidget for TestWidget {
                 flint_utils::env::load_from_file(&env_path)?;
             }
         }
-        let plugins = plugin::list_from_config(&toml);
+        let plugins = plugin::list_from_config(&toml)?;
 
-        let run_plugins: Vec<Plugin> = plugins
+        let mut run_plugins: Vec<Plugin> = plugins
             .clone()
             .iter()
             .filter(|plugin| plugin.kind != PluginKind::Report && plugin.kind != PluginKind::Ci)
@@ -83,94 +289,92 @@ impl AppWidget for TestWidget {
             .cloned()
             .collect();
 
-        let report_plugins: Arc<Vec<Plugin>> = Arc::new(
-            plugins
-                .iter()
-                .filter(|plugin| plugin.kind == PluginKind::Report)
-                .cloned()
-                .collect(),
-        );
+        let report_plugins: Vec<Plugin> = plugins
+            .iter()
+            .filter(|plugin| plugin.kind == PluginKind::Report)
+            .cloned()
+            .collect();
 
-        for plugin in run_plugins {
-            let plugin = plugin.clone();
-            let toml_clone = toml.clone();
-            let report_plugins = Arc::clone(&report_plugins); // Share report plugins across threads
-            let pool = self.thread_pool.as_ref().unwrap();
+        self.thresholds = toml.thresholds.clone();
 
-            pool.execute(move || {
-                info!("Testing with: {}", plugin.details.id);
-                let result = plugin.run(&toml_clone);
+        let changed_files = if self.args.changed {
+            let cwd = get_flag!(current_dir);
+            let files = git::changed_files(&cwd, self.args.since.as_deref())?;
+            info!("{} changed files", files.len());
 
-                if let Err(err) = result {
-                    error!("{}", err);
-                    return;
+            let by_plugin = plugin::files_by_plugin(&files);
+            run_plugins.retain(|plugin| {
+                let has_files =
+                    by_plugin.contains_key(&(plugin.kind.clone(), plugin.details.id.clone()));
+                if !has_files {
+                    info!("No changed files for {}, not running it", plugin.details.id);
                 }
+                has_files
+            });
+            Some(by_plugin)
+        } else {
+            None
+        };
 
-                let command = result.unwrap();
-
-                let cmd_output = std::process::Command::new(&command[0])
-                    .args(&command[1..])
-                    .current_dir(get_flag!(current_dir).as_path())
-                    .output();
-
-                info!("Running command: {:#?}", command);
+        self.plugins = run_plugins.clone();
+        let jobs = run_plugins
+            .iter()
+            .map(|plugin| Job::from_config(plugin, &toml))
+            .collect::<Vec<_>>();
+        let max_jobs = self
+            .args
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+        let thresholds = self.thresholds.clone();
+        let outcomes = Arc::clone(&self.outcomes);
+        let pool = self.thread_pool.as_ref().unwrap();
 
-                if let Err(e) = cmd_output {
-                    error!("Failed to execute command '{}': {}", command[0], e);
-                    return;
-                }
+        let resources = Arc::new(Resources::default());
+        let run = TestRun {
+            toml,
+            report_plugins,
+            resources: Arc::clone(&resources),
+            changed_files,
+            cache: ResultCache::new(&get_flag!(current_dir)),
+            use_cache: !self.args.no_cache,
+            baseline: if self.write_baseline {
+                None
+            } else {
+                Baseline::load(&Baseline::path(&get_flag!(current_dir)))?
+            },
+            write_baseline: self.write_baseline,
+            fixed: Arc::clone(&self.fixed),
+            suppression_warnings: Arc::clone(&self.suppression_warnings),
+        };
 
-                let output = cmd_output.unwrap();
-
-                let eval_result = plugin.eval(output, &toml_clone);
-
-                match eval_result {
-                    Err(e) => error!("Failed to evaluate plugin: {}", e),
-                    Ok(res) => {
-                        for report_plugin in report_plugins.iter() {
-                            info!("Running report plugin: {}", report_plugin.details.id);
-                            match report_plugin.report(&toml_clone, &res, &plugin.details.id) {
-                                Err(e) => {
-                                    error!("Report plugin error: {}", e);
-                                }
-                                Ok(res) => {
-                                    for (file_name, contents) in res {
-                                        let flint_path = get_flag!(current_dir);
-                                        let file_path = flint_path.join(&file_name);
-
-                                        if let Some(parent) = file_path.parent() {
-                                            if !parent.exists() {
-                                                fs::create_dir_all(parent).unwrap_or_else(|e| {
-                                                    error!(
-                                                        "Failed to create directory for {}: {}",
-                                                        file_name, e
-                                                    );
-                                                });
-                                            }
-                                        }
-
-                                        match std::fs::write(flint_path.join(&file_name), contents)
-                                        {
-                                            Ok(_) => (),
-                                            Err(e) => error!(
-                                                "Failed to write report file {}: {}",
-                                                file_name, e
-                                            ),
-                                        }
-
-                                        success!(
-                                            "Reported {} results to {} successfully",
-                                            plugin.details.id,
-                                            file_name
-                                        );
-                                    }
-                                }
-                            }
-                        }
+        // The scheduler waits for its jobs, so it gets a thread of its own
+        // rather than blocking the UI
+        pool.execute(move || {
+            schedule::run(
+                jobs,
+                max_jobs,
+                &thresholds,
+                resources,
+                move |plugin, guard| run_plugin(&run, plugin, guard),
+                |plugin, outcome| {
+                    let id = &plugin.details.id;
+                    let error = outcome.error();
+                    if let Some(error) = &error {
+                        events::emit(Event::Error {
+                            plugin: Some(id),
+                            message: error,
+                        });
                     }
-                }
-            });
-        }
+                    events::emit(Event::PluginFinished {
+                        plugin: id,
+                        kind: &plugin.kind,
+                        status: outcome.status(),
+                        error: error.as_deref(),
+                    });
+                    outcomes.lock().unwrap().push((id.clone(), outcome));
+                },
+            );
+        });
 
         Ok(())
     }
@@ -204,6 +408,348 @@ impl AppWidget for TestWidget {
             _ => Ok(()),
         })
     }
+
+    fn finish(&mut self) {
+        for warning in self.suppression_warnings.lock().unwrap().iter() {
+            warn!("{}", warning);
+        }
+
+        if self.write_baseline {
+            self.baseline_failed = !self.save_baseline();
+            return;
+        }
+
+        let fixed = self.fixed.lock().unwrap();
+        for entry in fixed.iter() {
+            info!(
+                "Fixed since the baseline: {} {}: {}",
+                entry.plugin, entry.file, entry.message
+            );
+        }
+        if !fixed.is_empty() {
+            warn!(
+                "{} baseline entries are fixed, run `flint baseline` to remove them from {}",
+                fixed.len(),
+                BASELINE_FILE
+            );
+        }
+
+        let outcomes = self.outcomes.lock().unwrap();
+        let verdict = Verdict::from_outcomes(&outcomes, &self.thresholds);
+
+        for skipped in &verdict.skipped {
+            warn!("{}", skipped);
+        }
+
+        for failure in verdict
+            .plugin_errors
+            .iter()
+            .chain(&verdict.lint_failures)
+            .chain(&verdict.test_failures)
+        {
+            error!("{}", failure);
+        }
+
+        if let Some(format) = self.args.format {
+            if let Err(err) = self.write_results(format, &outcomes) {
+                error!("Failed to write the {:?} results: {}", format, err);
+            }
+        }
+
+        if verdict.passed() {
+            success!("All checks passed");
+        } else {
+            error!("Checks failed (exit code {})", verdict.exit_code());
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        if self.write_baseline {
+            return if self.baseline_failed {
+                EXIT_PLUGIN_ERROR
+            } else {
+                0
+            };
+        }
+
+        let outcomes = self.outcomes.lock().unwrap();
+        Verdict::from_outcomes(&outcomes, &self.thresholds).exit_code()
+    }
+
+    fn summary(&self) -> Map<String, Value> {
+        let outcomes = self.outcomes.lock().unwrap();
+        let plugins = outcomes
+            .iter()
+            .map(|(plugin_id, outcome)| {
+                json!({
+                    "plugin": plugin_id,
+                    "kind": self
+                        .plugins
+                        .iter()
+                        .find(|plugin| &plugin.details.id == plugin_id)
+                        .map(|plugin| &plugin.kind),
+                    "status": outcome.status(),
+                    "error": outcome.error(),
+                    "output": outcome.output(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut summary = Map::new();
+        summary.insert("plugins".into(), plugins.into());
+        if !self.write_baseline {
+            let verdict = Verdict::from_outcomes(&outcomes, &self.thresholds);
+            let failures = verdict
+                .plugin_errors
+                .iter()
+                .chain(&verdict.lint_failures)
+                .chain(&verdict.test_failures)
+                .collect::<Vec<_>>();
+            summary.insert("passed".into(), verdict.passed().into());
+            summary.insert("failures".into(), json!(failures));
+        }
+        summary
+    }
+}
+
+fn run_plugin(run: &TestRun, plugin: &Plugin, guard: ResourceGuard) -> PluginOutcome {
+    events::emit(Event::PluginStarted {
+        plugin: &plugin.details.id,
+        kind: &plugin.kind,
+    });
+    let toml = &run.toml;
+    let cwd = get_flag!(current_dir);
+    let files = run
+        .changed_files
+        .as_ref()
+        .map(|by_plugin| by_plugin[&(plugin.kind.clone(), plugin.details.id.clone())].as_slice());
+
+    // Listed once, both the cache key and the suppression comments need them
+    let input_files = result_cache::input_files(plugin, toml, &cwd);
+    let cache_key = match &input_files {
+        Ok(input_files) => result_cache::cache_key(plugin, toml, &cwd, files, input_files),
+        Err(err) => Err(Error::Err(format!("unable to list its files: {}", err))),
+    };
+    let cache_key = match cache_key {
+        Ok(key) => Some(key),
+        Err(err) => {
+            warn!("Not caching the result of {}: {}", plugin.details.id, err);
+            None
+        }
+    };
+    // With --changed, only the changed files were checked
+    let checked = files.or(input_files.as_deref().ok()).unwrap_or_default();
+    let cached = cache_key
+        .as_ref()
+        .filter(|_| run.use_cache)
+        .and_then(|key| run.cache.get(plugin, key));
+    if let Some(res) = cached {
+        info!(
+            "Nothing changed for {}, using the cached result",
+            plugin.details.id
+        );
+        drop(guard);
+        return PluginOutcome::Evaluated(finish_result(run, plugin, res, files, checked, true));
+    }
+
+    info!("Testing with: {}", plugin.details.id);
+    let plan = match plugin.run(toml, files) {
+        Ok(plan) => plan,
+        Err(err) => {
+            error!("{}", err);
+            return PluginOutcome::Failed(err.to_string());
+        }
+    };
+
+    let limits = match RunLimits::from_config(plugin, toml) {
+        Ok(limits) => limits,
+        Err(err) => return PluginOutcome::Failed(err.to_string()),
+    };
+
+    info!("Running command: {}", plan);
+
+    let (res, timed_out) = match process::run_plan(&plugin.details.id, &plan, &cwd, &limits) {
+        Ok(PlanResult::Finished(outputs)) => match plugin.eval(&outputs, toml) {
+            Ok(res) => (res, None),
+            Err(e) => {
+                error!("Failed to evaluate plugin: {}", e);
+                return PluginOutcome::Failed(format!("failed to evaluate plugin: {}", e));
+            }
+        },
+        Ok(PlanResult::StepFailed(step)) => {
+            let message = format!(
+                "step {} failed with exit code {}",
+                step.name,
+                step.output
+                    .status
+                    .code()
+                    .map_or("unknown".to_string(), |code| code.to_string())
+            );
+            error!("{}: {}", plugin.details.id, message);
+            return PluginOutcome::Failed(message);
+        }
+        // Reports still get a (failed) result, so a hung tool doesn't silently vanish from them
+        Ok(PlanResult::TimedOut(timeout)) => {
+            let message = format!("timed out after {}s", timeout.as_secs_f64());
+            error!("{} {}", plugin.details.id, message);
+            (
+                PluginEvalOutput::failed(&plugin.kind, &plugin.details.id, message),
+                Some(timeout),
+            )
+        }
+        Err(e) => {
+            error!("Failed to execute command '{}': {}", plan, e);
+            return PluginOutcome::Failed(format!("failed to execute command '{}': {}", plan, e));
+        }
+    };
+
+    // The plugin's resources aren't needed for reporting, so let other plugins have them
+    drop(guard);
+
+    // A timeout says nothing about the files, so only real results are cached
+    if let (Some(key), None) = (&cache_key, timed_out) {
+        if let Err(err) = run.cache.put(plugin, key, &res) {
+            warn!(
+                "Failed to cache the result of {}: {}",
+                plugin.details.id, err
+            );
+        }
+    }
+
+    let res = finish_result(run, plugin, res, files, checked, false);
+    match timed_out {
+        Some(timeout) => PluginOutcome::TimedOut { timeout },
+        None => PluginOutcome::Evaluated(res),
+    }
+}
+
+/// Applies suppressions and the baseline to a plugin's result, and reports it.
+/// `checked` are the files the plugin was run on.
+fn finish_result(
+    run: &TestRun,
+    plugin: &Plugin,
+    res: PluginEvalOutput,
+    files: Option<&[String]>,
+    checked: &[String],
+    cached: bool,
+) -> PluginEvalOutput {
+    let res = apply_suppressions(run, plugin, res, checked);
+    let res = apply_baseline(run, plugin, res, files);
+    events::emit(Event::EvalResult {
+        plugin: &plugin.details.id,
+        cached,
+        output: &res,
+    });
+    report(run, plugin, &res, cached);
+    res
+}
+
+/// Leaves the issues covered by suppression comments out of a lint plugin's result
+fn apply_suppressions(
+    run: &TestRun,
+    plugin: &Plugin,
+    res: PluginEvalOutput,
+    checked: &[String],
+) -> PluginEvalOutput {
+    let PluginEvalOutput::Lint(output) = &res else {
+        return res;
+    };
+
+    let cwd = get_flag!(current_dir);
+    let applied = suppress::apply(&plugin.details.id, output, &cwd, checked);
+    if applied.suppressed > 0 {
+        info!(
+            "{}: left out {} issues with suppression comments",
+            plugin.details.id, applied.suppressed
+        );
+    }
+    run.suppression_warnings
+        .lock()
+        .unwrap()
+        .extend(applied.unused.into_iter().chain(applied.invalid));
+    PluginEvalOutput::Lint(applied.output)
+}
+
+/// Leaves the issues that are in the baseline out of a lint plugin's result
+fn apply_baseline(
+    run: &TestRun,
+    plugin: &Plugin,
+    res: PluginEvalOutput,
+    files: Option<&[String]>,
+) -> PluginEvalOutput {
+    let (Some(baseline), PluginEvalOutput::Lint(output)) = (&run.baseline, &res) else {
+        return res;
+    };
+
+    let applied = baseline.apply(&plugin.details.id, output, files);
+    if applied.baselined > 0 {
+        info!(
+            "{}: left out {} issues that are in the baseline",
+            plugin.details.id, applied.baselined
+        );
+    }
+    run.fixed.lock().unwrap().extend(applied.fixed);
+    PluginEvalOutput::Lint(applied.output)
+}
+
+fn report(run: &TestRun, plugin: &Plugin, res: &PluginEvalOutput, cached: bool) {
+    // The results only go to the baseline
+    if run.write_baseline {
+        return;
+    }
+
+    for report_plugin in run.report_plugins.iter() {
+        info!("Running report plugin: {}", report_plugin.details.id);
+        // Report plugins that write to a shared file can declare a resource to take turns
+        let _report_guard = match schedule::resources(report_plugin, &run.toml) {
+            Ok(names) => run.resources.acquire(&names),
+            Err(e) => {
+                error!("Report plugin error: {}", e);
+                events::emit(Event::Error {
+                    plugin: Some(&report_plugin.details.id),
+                    message: &e.to_string(),
+                });
+                continue;
+            }
+        };
+        match report_plugin.report(&run.toml, res, &plugin.details.id, cached) {
+            Err(e) => {
+                error!("Report plugin error: {}", e);
+                events::emit(Event::Error {
+                    plugin: Some(&report_plugin.details.id),
+                    message: &e.to_string(),
+                });
+            }
+            Ok(files) => {
+                for (file_name, contents) in files {
+                    let flint_path = get_flag!(current_dir);
+                    let file_path = flint_path.join(&file_name);
+
+                    if let Some(parent) = file_path.parent() {
+                        if !parent.exists() {
+                            fs::create_dir_all(parent).unwrap_or_else(|e| {
+                                error!("Failed to create directory for {}: {}", file_name, e);
+                            });
+                        }
+                    }
+
+                    match std::fs::write(&file_path, contents) {
+                        Ok(_) => events::emit(Event::FileWritten {
+                            plugin: Some(&report_plugin.details.id),
+                            path: &file_path,
+                        }),
+                        Err(e) => error!("Failed to write report file {}: {}", file_name, e),
+                    }
+
+                    success!(
+                        "Reported {} results to {} successfully",
+                        plugin.details.id,
+                        file_name
+                    );
+                }
+            }
+        }
+    }
 }
 
 impl WidgetRef for TestWidget {
@@ -18,26 +18,95 @@ fn main() {
     // #[cfg(not(debug_assertions))]
     {
         use app::{
-            help::HelpWidget, install::InstallWidget, test::TestWidget, AppWidget, AppWidgetArgs,
+            check_config::CheckConfigWidget, generate::GenerateWidget, init::InitWidget,
+            install::InstallWidget, plugin::PluginWidget, status::StatusWidget, test::TestWidget,
+            AppWidget, AppWidgetArgs,
         };
+        use flint_utils::{error, get_flag, output};
+        use plugin::verdict::EXIT_PLUGIN_ERROR;
+        use serde_json::Map;
         use threadpool::ThreadPool;
-        let subcommand = args.get(1).unwrap();
+        use util::events::{self, Event};
 
-        if ["test", "install"].contains(&subcommand.as_str()) {
+        let structured = get_flag!(output).is_structured();
+        let non_interactive_widget: Option<Box<dyn AppWidget>> = match app_args.command {
+            Some(AppWidgetArgs::Install(ref args)) => {
+                Some(Box::new(InstallWidget::new(args.clone())))
+            }
+            Some(AppWidgetArgs::Update(ref args)) => {
+                Some(Box::new(InstallWidget::update(args.clone())))
+            }
+            Some(AppWidgetArgs::Test(ref args)) => Some(Box::new(TestWidget::new(args.clone()))),
+            Some(AppWidgetArgs::Baseline(ref args)) => {
+                Some(Box::new(TestWidget::baseline(args.clone())))
+            }
+            Some(AppWidgetArgs::CheckConfig(ref args)) => {
+                Some(Box::new(CheckConfigWidget::new(args.clone())))
+            }
+            Some(AppWidgetArgs::Status(ref args)) => {
+                Some(Box::new(StatusWidget::new(args.clone())))
+            }
+            Some(AppWidgetArgs::Plugin(ref args)) => {
+                Some(Box::new(PluginWidget::new(args.clone())))
+            }
+            // Generating only shows logs, so there's nothing lost without the TUI
+            Some(AppWidgetArgs::Generate(ref args)) if structured || args.writes_nothing() => {
+                Some(Box::new(GenerateWidget::new(args.clone())))
+            }
+            Some(AppWidgetArgs::Init(ref args)) if args.yes => {
+                Some(Box::new(InitWidget::new(args.clone())))
+            }
+            _ => None,
+        };
+
+        let command = app_args
+            .command
+            .as_ref()
+            .map_or("help", AppWidgetArgs::name);
+        let summary = |exit_code: i32| {
+            let mut summary = Map::new();
+            summary.insert("command".into(), command.into());
+            summary.insert("exit_code".into(), exit_code.into());
+            summary
+        };
+
+        if non_interactive_widget.is_none() && structured {
+            set_flag!(non_interactive, true);
+            let mut message = format!("{} needs the terminal UI, it can't print JSON", command);
+            if command == "init" {
+                message += ", use init --yes";
+            }
+            events::emit(Event::Error {
+                plugin: None,
+                message: &message,
+            });
+            output::finish(summary(2));
+            std::process::exit(2);
+        }
+
+        if let Some(mut non_interactive_widget) = non_interactive_widget {
             set_flag!(non_interactive, true);
-            let mut non_interactive_widget: Box<dyn AppWidget> = match app_args.command.unwrap() {
-                AppWidgetArgs::Install(args) => Box::new(InstallWidget::new(args)),
-                AppWidgetArgs::Test(args) => Box::new(TestWidget::new(args)),
-                _ => Box::new(HelpWidget::default()),
-            };
 
             let thread_pool = ThreadPool::new(16);
             non_interactive_widget.set_thread_pool(&thread_pool);
 
-            non_interactive_widget.setup().unwrap();
+            if let Err(err) = non_interactive_widget.setup() {
+                error!("{}", err);
+                events::emit(Event::Error {
+                    plugin: None,
+                    message: &err.to_string(),
+                });
+                output::finish(summary(EXIT_PLUGIN_ERROR));
+                std::process::exit(EXIT_PLUGIN_ERROR);
+            }
 
             thread_pool.join();
-            return;
+            non_interactive_widget.finish();
+            let exit_code = non_interactive_widget.exit_code();
+            let mut summary = summary(exit_code);
+            summary.extend(non_interactive_widget.summary());
+            output::finish(summary);
+            std::process::exit(exit_code);
         }
     }
 
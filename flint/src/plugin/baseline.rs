@@ -0,0 +1,218 @@
+use flint_utils::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::exec::eval::{LintPluginEvalOutput, TestCaseOutput};
+
+pub const BASELINE_FILE: &str = "flint-baseline.json";
+
+/// A known lint issue, and how many times it occurs in its file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub plugin: String,
+    pub file: String,
+    pub rule_id: Option<String>,
+    /// The normalized message
+    pub message: String,
+    pub count: u32,
+}
+
+/// Lint issues that already existed when `flint baseline` was run. `flint test`
+/// only reports and fails on issues that aren't in it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Baseline {
+    pub issues: Vec<BaselineEntry>,
+}
+
+/// A plugin's lint output with the baselined issues taken out
+#[derive(Debug, Clone)]
+pub struct Applied {
+    pub output: LintPluginEvalOutput,
+    /// How many of the plugin's issues were in the baseline
+    pub baselined: u32,
+    /// Baseline entries (with the number of occurrences) that don't occur anymore
+    pub fixed: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(BASELINE_FILE)
+    }
+
+    /// The baseline in `path`, if there is one
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        // Sorted, so regenerating the baseline gives a readable diff
+        self.issues.sort_by(|a, b| {
+            (&a.plugin, &a.file, &a.rule_id, &a.message)
+                .cmp(&(&b.plugin, &b.file, &b.rule_id, &b.message))
+        });
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    pub fn for_plugin<'a>(&'a self, plugin_id: &'a str) -> impl Iterator<Item = &'a BaselineEntry> {
+        self.issues
+            .iter()
+            .filter(move |entry| entry.plugin == plugin_id)
+    }
+
+    /// Adds every issue in a plugin's output
+    pub fn add(&mut self, plugin_id: &str, output: &LintPluginEvalOutput) {
+        for result in issues(output) {
+            let entry = entry(plugin_id, result);
+            match self
+                .issues
+                .iter_mut()
+                .find(|other| other.fingerprint == entry.fingerprint)
+            {
+                Some(other) => other.count += 1,
+                None => self.issues.push(entry),
+            }
+        }
+    }
+
+    /// Takes the baselined issues out of a plugin's output. With `files`, only
+    /// baseline entries for those files can count as fixed.
+    pub fn apply(
+        &self,
+        plugin_id: &str,
+        output: &LintPluginEvalOutput,
+        files: Option<&[String]>,
+    ) -> Applied {
+        let mut allowed = self
+            .for_plugin(plugin_id)
+            .map(|entry| (entry.fingerprint.clone(), entry.count))
+            .collect::<HashMap<_, _>>();
+
+        let mut baselined = 0;
+        let mut lint_results = Vec::new();
+        for result in &output.lint_results {
+            if !result.success {
+                let fingerprint = entry(plugin_id, result).fingerprint;
+                if let Some(count) = allowed.get_mut(&fingerprint).filter(|count| **count > 0) {
+                    *count -= 1;
+                    baselined += 1;
+                    continue;
+                }
+            }
+            lint_results.push(result.clone());
+        }
+
+        let fixed = self
+            .for_plugin(plugin_id)
+            .filter(|entry| files.is_none_or(|files| files.contains(&entry.file)))
+            .filter_map(|entry| {
+                let count = allowed[&entry.fingerprint];
+                (count > 0).then(|| BaselineEntry {
+                    count,
+                    ..entry.clone()
+                })
+            })
+            .collect();
+
+        Applied {
+            output: LintPluginEvalOutput {
+                total_errors: output.total_errors.saturating_sub(baselined),
+                lint_results,
+            },
+            baselined,
+            fixed,
+        }
+    }
+}
+
+fn issues(output: &LintPluginEvalOutput) -> impl Iterator<Item = &TestCaseOutput> {
+    output.lint_results.iter().filter(|result| !result.success)
+}
+
+fn entry(plugin_id: &str, result: &TestCaseOutput) -> BaselineEntry {
+    let rule_id = result.rule();
+    let message = normalize(result.error_message.as_deref().unwrap_or_default());
+
+    let mut hasher = Sha256::new();
+    for field in [
+        plugin_id,
+        &result.file_name,
+        rule_id.as_deref().unwrap_or_default(),
+        &message,
+    ] {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+    let fingerprint = hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    BaselineEntry {
+        fingerprint,
+        plugin: plugin_id.to_string(),
+        file: result.file_name.clone(),
+        rule_id,
+        message,
+        count: 1,
+    }
+}
+
+/// Collapses whitespace and replaces numbers with `N`, so messages like
+/// "Line is 130 characters long" don't change whenever the code does
+fn normalize(message: &str) -> String {
+    let mut normalized = String::new();
+    let mut in_number = false;
+    for word in message.split_whitespace() {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        for c in word.chars() {
+            if c.is_ascii_digit() {
+                if !in_number {
+                    normalized.push('N');
+                }
+                in_number = true;
+            } else {
+                normalized.push(c);
+                in_number = false;
+            }
+        }
+        in_number = false;
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_replaces_numbers() {
+        assert_eq!(
+            normalize("Line is 130 characters long (max 100)"),
+            "Line is N characters long (max N)"
+        );
+        assert_eq!(normalize("v1.23.4"), "vN.N.N");
+        assert_eq!(normalize("abc123def"), "abcNdef");
+    }
+
+    #[test]
+    fn normalize_collapses_whitespace() {
+        assert_eq!(normalize("  unused \t variable\n x "), "unused variable x");
+        assert_eq!(normalize("1 2"), "N N");
+        assert_eq!(normalize(""), "");
+    }
+}
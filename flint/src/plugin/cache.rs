@@ -0,0 +1,421 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use flint_utils::{app_err, cmd, flags::cache_dir, info, warn, Result};
+use sha2::{Digest, Sha256};
+
+use super::download::copy_dir_all;
+
+/// A git repo cached under the flint data directory. Plugins are extracted
+/// into `commits/<commit>/<dir>`, so once a commit has been downloaded it
+/// never needs to be fetched again, whichever project asks for it.
+///
+/// ```text
+/// <cache_dir>/<hash of repo url>/
+///     source      the repo url
+///     refs.toml   last commit each branch or tag resolved to, used by --offline
+///     repo/       blobless, sparse clone used to fetch new commits
+///     commits/<commit>/<dir>/
+/// ```
+pub struct RepoCache {
+    url: String,
+    root: PathBuf,
+}
+
+impl RepoCache {
+    pub fn open(url: &str) -> Result<Self> {
+        let digest = Sha256::digest(url.as_bytes());
+        let key = digest
+            .iter()
+            .take(8)
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+
+        let root = cache_dir().join(key);
+        fs::create_dir_all(&root)?;
+        fs::write(root.join("source"), url)?;
+
+        Ok(Self {
+            url: url.to_string(),
+            root,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn repo_path(&self) -> PathBuf {
+        self.root.join("repo")
+    }
+
+    fn refs_path(&self) -> PathBuf {
+        self.root.join("refs.toml")
+    }
+
+    /// Where a plugin directory from a given commit is (or will be) stored
+    pub fn plugin_path(&self, commit: &str, dir: &str) -> PathBuf {
+        self.root.join("commits").join(commit).join(dir)
+    }
+
+    /// Clones the repo, or fetches new commits if it has been cloned before
+    pub fn sync(&self) -> Result<()> {
+        let repo = self.repo_path();
+
+        let git_check = cmd!["git", "--help"].output()?;
+        if !git_check.status.success() {
+            return app_err!("Git is not available or not working properly");
+        }
+
+        if repo.join(".git").exists() {
+            info!("Fetching {}", self.url);
+            let output = cmd![
+                "git",
+                "fetch",
+                "--filter=blob:none",
+                "--tags",
+                "--force",
+                "origin"
+            ]
+            .current_dir(&repo)
+            .output()?;
+            git_stdout(output)?;
+            return Ok(());
+        }
+
+        info!("Cloning repository metadata from {}", self.url);
+        if repo.exists() {
+            // Leftovers from an interrupted clone
+            fs::remove_dir_all(&repo)?;
+        }
+
+        let output = cmd![
+            "git",
+            "clone",
+            "--filter=blob:none",
+            "--no-checkout",
+            "--sparse",
+            "--",
+            &self.url,
+            &repo
+        ]
+        .output()?;
+
+        if !output.status.success() {
+            let _ = fs::remove_dir_all(&repo);
+            return app_err!(
+                "Failed to clone repository.\n Git Clone output: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Resolves a branch, tag or commit to a commit using the (synced) repo,
+    /// and remembers the result for offline installs
+    pub fn resolve(&self, reference: &str) -> Result<String> {
+        check_ref(reference)?;
+        let repo = self.repo_path();
+
+        // Branches only exist as remote refs after cloning, tags and commits resolve as they are
+        for candidate in [format!("origin/{}", reference), reference.to_string()] {
+            let output = cmd![
+                "git",
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                format!("{}^{{commit}}", candidate)
+            ]
+            .current_dir(&repo)
+            .output()?;
+
+            if output.status.success() {
+                let commit = git_stdout(output)?;
+                self.save_ref(reference, &commit)?;
+                return Ok(commit);
+            }
+        }
+
+        self.fetch_commit(reference)?;
+        let commit = git_stdout(
+            cmd!["git", "rev-parse", "FETCH_HEAD^{commit}"]
+                .current_dir(&repo)
+                .output()?,
+        )?;
+        self.save_ref(reference, &commit)?;
+        Ok(commit)
+    }
+
+    /// The commit a branch or tag resolved to the last time we were online
+    pub fn resolve_offline(&self, reference: &str) -> Result<String> {
+        match self.load_refs().remove(reference) {
+            Some(commit) => Ok(commit),
+            None => app_err!(
+                "{} of {} is not in the plugin cache, run `flint install` once without --offline",
+                reference,
+                self.url
+            ),
+        }
+    }
+
+    // Only called with full commit hashes
+    fn has_commit(&self, commit: &str) -> bool {
+        cmd!["git", "cat-file", "-e", format!("{}^{{commit}}", commit)]
+            .current_dir(self.repo_path())
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    // Commits that aren't reachable from any branch (e.g. after a force push) have to be fetched directly
+    fn fetch_commit(&self, reference: &str) -> Result<()> {
+        check_ref(reference)?;
+        warn!(
+            "{} not found in the cloned refs, fetching it directly",
+            reference
+        );
+        let output = cmd!["git", "fetch", "--filter=blob:none", "origin", reference]
+            .current_dir(self.repo_path())
+            .output()?;
+
+        if !output.status.success() {
+            return app_err!(
+                "Could not find branch, tag or commit {}.\n Git fetch output: {}",
+                reference,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks out the given directories at `commit` and stores them in the cache
+    pub fn extract(&self, commit: &str, dirs: &[&String]) -> Result<()> {
+        if !is_commit_hash(commit) {
+            return app_err!("{:?} is not a full commit hash", commit);
+        }
+        let repo = self.repo_path();
+        if !self.has_commit(commit) {
+            self.fetch_commit(commit)?;
+        }
+
+        info!("Setting up sparse checkout for {} plugin paths", dirs.len());
+        let output = cmd!["git", "sparse-checkout", "set", "--"]
+            .current_dir(&repo)
+            .args(dirs)
+            .output()?;
+        if !output.status.success() {
+            return app_err!(
+                "Failed to set sparse-checkout.\n Git output: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        info!("Checking out {}", commit);
+        let output = cmd!["git", "checkout", "--detach", "--force", commit, "--"]
+            .current_dir(&repo)
+            .output()?;
+        if !output.status.success() {
+            return app_err!(
+                "Failed to check out {}.\n Git checkout output: {}",
+                commit,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        for dir in dirs {
+            let src = repo.join(dir);
+            if !src.exists() {
+                warn!("{} does not exist in {} at {}", dir, self.url, commit);
+                continue;
+            }
+
+            // Copy next to the final location and rename, so an interrupted copy
+            // never leaves a half written plugin in the cache
+            let dest = self.plugin_path(commit, dir);
+            let partial = dest.with_file_name(format!(
+                "{}.partial",
+                dest.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            if partial.exists() {
+                fs::remove_dir_all(&partial)?;
+            }
+            copy_dir_all(&src, &partial)?;
+            if dest.exists() {
+                fs::remove_dir_all(&dest)?;
+            }
+            fs::rename(&partial, &dest)?;
+        }
+
+        Ok(())
+    }
+
+    fn load_refs(&self) -> BTreeMap<String, String> {
+        fs::read_to_string(self.refs_path())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_ref(&self, reference: &str, commit: &str) -> Result<()> {
+        let mut refs = self.load_refs();
+        refs.insert(reference.to_string(), commit.to_string());
+        fs::write(self.refs_path(), toml::to_string(&refs)?)?;
+        Ok(())
+    }
+}
+
+/// Branches, tags and commits are passed to git as arguments, where one
+/// starting with `-` would be taken for an option
+pub fn check_ref(reference: &str) -> Result<()> {
+    if reference.is_empty() || reference.starts_with('-') {
+        return app_err!("{:?} is not a valid branch, tag or commit", reference);
+    }
+    Ok(())
+}
+
+/// Whether `commit` is a full SHA-1 or SHA-256 hash, as flint.lock pins them
+pub fn is_commit_hash(commit: &str) -> bool {
+    matches!(commit.len(), 40 | 64) && commit.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub fn git_stdout(output: Output) -> Result<String> {
+    if !output.status.success() {
+        return app_err!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args([
+                "-c",
+                "user.name=flint",
+                "-c",
+                "user.email=flint@example.com",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit(work: &Path, message: &str) -> String {
+        fs::create_dir_all(work.join("lint/x-lint")).unwrap();
+        fs::write(work.join("lint/x-lint/details.lua"), message).unwrap();
+        git(work, &["add", "-A"]);
+        git(work, &["commit", "-m", message]);
+        git(work, &["push", "--quiet", "origin", "HEAD:main", "--tags"]);
+        git(work, &["rev-parse", "HEAD"])
+    }
+
+    /// A bare repo to clone from, a clone to push commits to it with, and a cache
+    fn setup() -> (TempDir, PathBuf, RepoCache) {
+        let tmp = TempDir::new().unwrap();
+        let origin = tmp.path().join("origin.git");
+        let work = tmp.path().join("work");
+        git(
+            tmp.path(),
+            &["init", "--quiet", "--bare", "-b", "main", "origin.git"],
+        );
+        git(tmp.path(), &["init", "--quiet", "-b", "main", "work"]);
+        git(
+            &work,
+            &["remote", "add", "origin", origin.to_str().unwrap()],
+        );
+
+        let cache = RepoCache {
+            url: format!("file://{}", origin.display()),
+            root: tmp.path().join("cache"),
+        };
+        fs::create_dir_all(&cache.root).unwrap();
+        (tmp, work, cache)
+    }
+
+    #[test]
+    fn resolves_branches_tags_and_commits() {
+        let (_tmp, work, cache) = setup();
+        let first = commit(&work, "first");
+        git(&work, &["tag", "v1"]);
+        let second = commit(&work, "second");
+        cache.sync().unwrap();
+
+        assert_eq!(cache.resolve("main").unwrap(), second);
+        assert_eq!(cache.resolve("v1").unwrap(), first);
+        assert_eq!(cache.resolve(&first).unwrap(), first);
+        assert!(cache.resolve("missing").is_err());
+    }
+
+    #[test]
+    fn follows_branches_after_a_sync() {
+        let (_tmp, work, cache) = setup();
+        let first = commit(&work, "first");
+        cache.sync().unwrap();
+        assert_eq!(cache.resolve("main").unwrap(), first);
+
+        let second = commit(&work, "second");
+        cache.sync().unwrap();
+        assert_eq!(cache.resolve("main").unwrap(), second);
+    }
+
+    #[test]
+    fn resolves_offline_to_the_last_resolved_commit() {
+        let (_tmp, work, cache) = setup();
+        let first = commit(&work, "first");
+        cache.sync().unwrap();
+        cache.resolve("main").unwrap();
+
+        // Not synced, so only the remembered commit is known
+        commit(&work, "second");
+        assert_eq!(cache.resolve_offline("main").unwrap(), first);
+        assert!(cache.resolve_offline("v1").is_err());
+    }
+
+    #[test]
+    fn rejects_refs_that_look_like_options() {
+        let (tmp, work, cache) = setup();
+        commit(&work, "first");
+        cache.sync().unwrap();
+
+        assert!(cache.resolve("--upload-pack=touch pwned").is_err());
+        assert!(cache.resolve("").is_err());
+        let dir = "lint/x-lint".to_string();
+        assert!(cache.extract("--output=pwned", &[&dir]).is_err());
+        assert!(cache.extract("main", &[&dir]).is_err());
+        assert!(!tmp.path().join("cache/repo/pwned").exists());
+    }
+
+    #[test]
+    fn recognizes_full_commit_hashes() {
+        assert!(is_commit_hash(&"a".repeat(40)));
+        assert!(is_commit_hash(&"0123456789abcdef".repeat(4)));
+        assert!(!is_commit_hash("abc1234"));
+        assert!(!is_commit_hash(&format!("-{}", "a".repeat(39))));
+        assert!(!is_commit_hash(&"g".repeat(40)));
+    }
+
+    #[test]
+    fn extracts_plugin_dirs() {
+        let (_tmp, work, cache) = setup();
+        let first = commit(&work, "first");
+        cache.sync().unwrap();
+
+        let dir = "lint/x-lint".to_string();
+        cache.extract(&first, &[&dir]).unwrap();
+        let details = cache.plugin_path(&first, &dir).join("details.lua");
+        assert_eq!(fs::read_to_string(details).unwrap(), "first");
+    }
+}
@@ -0,0 +1,195 @@
+use flint_ffi::add_ffi_modules;
+use flint_utils::{warn, Result};
+use mlua::{Function, Lua, LuaSerdeExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use super::{Plugin, PluginKind};
+use crate::util::toml::Config;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Support {
+    #[default]
+    Full,
+    Partial,
+}
+
+/// How a plugin translates one key of `[rules.common]`, as declared by `CommonRules()` in details.lua
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommonRule {
+    #[serde(default)]
+    pub support: Support,
+    /// The tool's own rules the key is translated to
+    #[serde(default)]
+    pub rules: Vec<String>,
+    /// What isn't honoured, for partial support
+    pub note: Option<String>,
+}
+
+pub type CommonRules = BTreeMap<String, CommonRule>;
+
+pub fn get_common_rules(plugin: &Plugin) -> Result<Option<CommonRules>> {
+    let lua = Lua::new();
+    add_ffi_modules(&lua)?;
+
+    plugin.load_file(&lua, "details.lua")?;
+
+    // Plugins aren't required to declare which common rules they support
+    let common_rules_fn: Function = match lua.globals().get("CommonRules") {
+        Ok(func) => func,
+        Err(_) => return Ok(None),
+    };
+
+    let value = common_rules_fn
+        .call::<mlua::Value>(())
+        .map_err(|err| plugin.runtime_error("details.lua", err))?;
+    let common_rules: CommonRules = lua.from_value(value).map_err(|err| {
+        plugin.contract_error(
+            "details.lua",
+            format!("invalid CommonRules() output: {}", err),
+        )
+    })?;
+    Ok(Some(common_rules))
+}
+
+/// Whether a linter enforces a key of `[rules.common]`
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Coverage {
+    Honoured,
+    Partial,
+    Unsupported,
+    /// The plugin doesn't declare what it supports
+    Unknown,
+}
+
+impl Coverage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Coverage::Honoured => "honoured",
+            Coverage::Partial => "partial",
+            Coverage::Unsupported => "unsupported",
+            Coverage::Unknown => "unknown",
+        }
+    }
+}
+
+/// The keys set in `[rules.common]`, against what each configured linter does with them
+pub struct CommonRulesMatrix {
+    keys: Vec<String>,
+    /// Linter ids and their declared common rules, if any
+    linters: Vec<(String, Option<CommonRules>)>,
+}
+
+impl CommonRulesMatrix {
+    pub fn new(toml: &Config, plugins: &[Plugin]) -> Self {
+        let keys = toml
+            .rules
+            .get("common")
+            .and_then(|common| common.as_table())
+            .map(|common| {
+                let mut keys = common.keys().cloned().collect::<Vec<_>>();
+                keys.sort();
+                keys
+            })
+            .unwrap_or_default();
+
+        let linters = plugins
+            .iter()
+            .filter(|plugin| plugin.kind == PluginKind::Lint)
+            .map(|plugin| {
+                let common_rules = plugin.get_common_rules().unwrap_or_else(|err| {
+                    warn!(
+                        "Unable to load common rules of plugin {}: {}",
+                        plugin.details.id, err
+                    );
+                    None
+                });
+                (plugin.details.id.clone(), common_rules)
+            })
+            .collect();
+
+        Self { keys, linters }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() || self.linters.is_empty()
+    }
+
+    fn rule<'a>(
+        common_rules: &'a Option<CommonRules>,
+        key: &str,
+    ) -> (Coverage, Option<&'a CommonRule>) {
+        let Some(common_rules) = common_rules else {
+            return (Coverage::Unknown, None);
+        };
+        match common_rules.get(key) {
+            Some(rule) if rule.support == Support::Partial => (Coverage::Partial, Some(rule)),
+            Some(rule) => (Coverage::Honoured, Some(rule)),
+            None => (Coverage::Unsupported, None),
+        }
+    }
+
+    /// A table with a row per key and a column per linter, followed by the notes
+    /// on partially honoured keys
+    pub fn render(&self) -> String {
+        let key_width = self
+            .keys
+            .iter()
+            .map(String::len)
+            .chain(["[rules.common]".len()])
+            .max()
+            .unwrap_or_default();
+        let widths = self
+            .linters
+            .iter()
+            .map(|(id, _)| id.len().max(Coverage::Unsupported.as_str().len()))
+            .collect::<Vec<_>>();
+
+        let mut lines = Vec::new();
+        let mut header = format!("{:<key_width$}", "[rules.common]");
+        for ((id, _), width) in self.linters.iter().zip(&widths) {
+            header += &format!("  {:<width$}", id);
+        }
+        lines.push(header.trim_end().to_string());
+
+        let mut notes = Vec::new();
+        for key in &self.keys {
+            let mut line = format!("{:<key_width$}", key);
+            for ((id, common_rules), width) in self.linters.iter().zip(&widths) {
+                let (coverage, rule) = Self::rule(common_rules, key);
+                line += &format!("  {:<width$}", coverage.as_str());
+                if let Some(note) = rule.and_then(|rule| rule.note.as_ref()) {
+                    notes.push(format!("{} ({}): {}", key, id, note));
+                }
+            }
+            lines.push(line.trim_end().to_string());
+        }
+
+        lines.extend(notes);
+        lines.join("\n")
+    }
+
+    /// `{ key: { linter: { coverage, rules, note } } }`, for `--output json`
+    pub fn to_json(&self) -> Value {
+        let mut keys = Map::new();
+        for key in &self.keys {
+            let mut linters = Map::new();
+            for (id, common_rules) in &self.linters {
+                let (coverage, rule) = Self::rule(common_rules, key);
+                linters.insert(
+                    id.clone(),
+                    json!({
+                        "coverage": coverage,
+                        "rules": rule.map(|rule| rule.rules.clone()).unwrap_or_default(),
+                        "note": rule.and_then(|rule| rule.note.clone()),
+                    }),
+                );
+            }
+            keys.insert(key.clone(), Value::Object(linters));
+        }
+        Value::Object(keys)
+    }
+}
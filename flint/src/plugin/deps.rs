@@ -16,28 +16,24 @@ pub fn get_dependencies(plugin: &Plugin) -> Result<HashMap<String, Vec<Dependenc
     let lua = Lua::new();
     add_ffi_modules(&lua)?;
 
-    let deps_func: Result<Function> = {
-        let contents = std::fs::read_to_string(plugin.path.join("details.lua"))?;
-        lua.load(&contents).exec()?;
-
-        // Try to get the `Dependencies` function from lua globals
-        match lua.globals().get("Dependencies") {
-            Ok(func) => Ok(func),
-            Err(_) => {
-                // Return empty deps if no Dependencies function exists
-                return Ok(HashMap::new());
-            }
-        }
+    plugin.load_file(&lua, "details.lua")?;
+
+    // Plugins aren't required to declare dependencies
+    let deps_func: Function = match lua.globals().get("Dependencies") {
+        Ok(func) => func,
+        Err(_) => return Ok(HashMap::new()),
     };
 
-    if let Ok(func) = deps_func {
-        let deps_value = func.call::<mlua::Value>(()).unwrap();
-        let deps: HashMap<String, Vec<Dependency>> = lua.from_value(deps_value).unwrap();
-        Ok(deps)
-    } else {
-        // Return empty deps if no dependencies.lua exists
-        Ok(HashMap::new())
-    }
+    let deps_value = deps_func
+        .call::<mlua::Value>(())
+        .map_err(|err| plugin.runtime_error("details.lua", err))?;
+    let deps: HashMap<String, Vec<Dependency>> = lua.from_value(deps_value).map_err(|err| {
+        plugin.contract_error(
+            "details.lua",
+            format!("invalid Dependencies() output: {}", err),
+        )
+    })?;
+    Ok(deps)
 }
 
 pub fn collect_dependencies(plugins: &Vec<Plugin>) -> Result<HashMap<String, Vec<Dependency>>> {
@@ -1,19 +1,54 @@
-use std::fs;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, TryLockError};
 use std::path::{Path, PathBuf};
 
-use flint_utils::{app_err, cmd, error, get_flag, info, success, warn};
+use flint_utils::{app_err, get_flag, info, success, warn};
 
-use crate::util::toml::Config;
+use crate::util::{
+    events::{self, Event},
+    toml::{Config, PluginSource},
+};
 
+use super::cache::{is_commit_hash, RepoCache};
+use super::lock::{checksum_dir, LockedPlugin, Lockfile};
 use super::PluginKind;
 use flint_utils::Result;
 
+const PLUGINS_REPO: &str = "https://github.com/skadewdl3/flint";
+/// Held while installing, not to be confused with the flint.lock pin file
+const LOCK_FILE: &str = ".install.lock";
+
+/// Which plugins are allowed to move away from the commit pinned in flint.lock
+#[derive(Debug, Clone)]
+pub enum Pins {
+    /// Install exactly what flint.lock records. Plugins without an entry get the latest commit of their ref.
+    Locked,
+    /// Move the given plugins (or every plugin, if empty) to the latest commit of their ref
+    Update(Vec<String>),
+}
+
+impl Pins {
+    fn is_updating(&self, id: &str) -> bool {
+        match self {
+            Pins::Locked => false,
+            Pins::Update(ids) => ids.is_empty() || ids.iter().any(|update_id| update_id == id),
+        }
+    }
+}
+
+/// Installs the given plugins (pairs of plugin id and its directory in the repo) at `rev`,
+/// or the latest commit of `reference` if there is no pin. Plugins come from the plugin cache,
+/// and only what is missing from it is downloaded. `checksums` holds the expected checksum of
+/// plugins that are pinned in flint.lock, these are verified before anything is copied.
 pub fn clone_plugin_folders(
     repo_url: &str,
     plugin_kind: PluginKind,
-    plugin_ids: Vec<&String>,
-    branch: &str,
-) -> Result<PathBuf> {
+    plugins: Vec<(&String, String)>,
+    reference: &str,
+    rev: Option<&str>,
+    checksums: &HashMap<String, String>,
+    offline: bool,
+) -> Result<Vec<LockedPlugin>> {
     info!(
         "Starting plugin clone process for {} plugins",
         plugin_kind.to_string()
@@ -24,13 +59,6 @@ pub fn clone_plugin_folders(
 
     info!("Downloading plugins to: {}", final_dest_path.display());
 
-    // Create a temporary directory for git operations
-    // TODO: Use ProjDirs crate here instead of std::env::temp_dir()
-    let temp_path = std::env::temp_dir().join("flint-plugins-temp");
-
-    // Create temporary directory
-    fs::create_dir_all(&temp_path)?;
-
     // Create the kind subfolder
     let kind_str = &plugin_kind.to_string();
     let kind_path = final_dest_path.join(kind_str);
@@ -38,132 +66,215 @@ pub fn clone_plugin_folders(
     // Make sure the final plugin type directory exists
     fs::create_dir_all(&kind_path)?;
 
-    // Check if git is installed and available
-    info!("Checking if git is installed");
-    let git_check = cmd!["git", "--help"].output()?;
-
-    if !git_check.status.success() {
-        return app_err!("Git is not available or not working properly");
-    }
-
-    info!("Cloning repository metadata from {}", repo_url);
-
-    // Do git operations in the temporary path
-    // Define a helper macro for creating commands
-
-    let output = cmd![
-        "git",
-        "clone",
-        "--filter=blob:none",
-        "--sparse",
-        "--branch",
-        branch,
-        repo_url,
-        &temp_path
-    ]
-    .output()?;
+    let cache = RepoCache::open(repo_url)?;
+    let _cache_lock = lock_dir(cache.root())?;
+    let mut synced = false;
+
+    let commit = match rev {
+        // flint.lock is edited by hand at times, and a commit is passed to git as is
+        Some(rev) if !is_commit_hash(rev) => {
+            return app_err!(
+                "flint.lock pins {} at {:?}, which is not a full commit hash",
+                repo_url,
+                rev
+            );
+        }
+        Some(rev) => rev.to_string(),
+        None if offline => cache.resolve_offline(reference)?,
+        None => {
+            cache.sync()?;
+            synced = true;
+            cache.resolve(reference)?
+        }
+    };
+    info!("Using {} at commit {}", reference, commit);
 
-    let _stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let status = output.status;
+    let missing: Vec<&String> = plugins
+        .iter()
+        .map(|(_, dir)| dir)
+        .filter(|dir| !cache.plugin_path(&commit, dir).exists())
+        .collect();
 
-    if !status.success() {
-        info!(
-            "Cleaning up temporary directory after failed clone: {}",
-            temp_path.display()
+    if missing.is_empty() {
+        info!("All plugins found in the plugin cache");
+    } else if offline {
+        return app_err!(
+            "{} at {} is not in the plugin cache, run `flint install` once without --offline",
+            missing
+                .iter()
+                .map(|dir| dir.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            commit
         );
-        _ = fs::remove_dir_all(&temp_path)
-            .map_err(|e| -> Result<()> { app_err!("Failed to remove temporary directory: {}", e) });
-
-        return app_err!("Failed to clone repository.\n Git Clone output: {}", stderr);
+    } else {
+        if !synced {
+            cache.sync()?;
+        }
+        cache.extract(&commit, &missing)?;
     }
 
-    if !status.success() {
-        // Clean up the temporary directory if clone fails
-        return app_err!("Failed to clone repository");
-    }
+    // Verify every pinned plugin before touching the plugins directory
+    let mut locked_plugins = Vec::new();
+    for (id, dir) in &plugins {
+        let src_plugin_path = cache.plugin_path(&commit, dir);
+        if !src_plugin_path.exists() {
+            warn!("Plugin not found: {}", dir);
+            let message = format!("{} not found in {} at {}", dir, repo_url, commit);
+            events::emit(Event::Error {
+                plugin: Some(id),
+                message: &message,
+            });
+            events::emit(Event::PluginFinished {
+                plugin: id,
+                kind: &plugin_kind,
+                status: "failed",
+                error: Some(&message),
+            });
+            continue;
+        }
+
+        let checksum = checksum_dir(&src_plugin_path)?;
+        if let Some(expected) = checksums.get(id.as_str()) {
+            if expected != &checksum {
+                let message = format!(
+                    "Checksum mismatch for {} plugin {} at {}: flint.lock has {}, downloaded {}. Run `flint update {}` if this change is expected.",
+                    kind_str,
+                    id,
+                    commit,
+                    expected,
+                    checksum,
+                    id
+                );
+                events::emit(Event::Error {
+                    plugin: Some(id),
+                    message: &message,
+                });
+                return app_err!("{}", message);
+            }
+        }
 
-    let mut sparse_paths = Vec::new();
-    for id in &plugin_ids {
-        sparse_paths.push(format!("flint-plugins/{}/{}", kind_str, id));
+        let locked = LockedPlugin {
+            id: id.to_string(),
+            kind: plugin_kind.clone(),
+            source: repo_url.to_string(),
+            reference: reference.to_string(),
+            rev: commit.clone(),
+            checksum,
+        };
+        locked_plugins.push((locked, src_plugin_path));
     }
 
-    info!(
-        "Metadata downloaded. Setting up sparse checkout for {} plugin paths",
-        sparse_paths.len()
-    );
-    let output = cmd!["git", "sparse-checkout", "set"]
-        .current_dir(&temp_path)
-        .args(&sparse_paths)
-        .output()?;
+    // Copy each requested plugin to the final destination
+    for (locked, src_plugin_path) in &locked_plugins {
+        install_plugin(src_plugin_path, &kind_path.join(&locked.id))?;
+        events::emit(Event::PluginFinished {
+            plugin: &locked.id,
+            kind: &plugin_kind,
+            status: "installed",
+            error: None,
+        });
+    }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let status = output.status;
+    success!("Successfully installed {} plugins", kind_path.display());
 
-    if !stdout.is_empty() {
-        info!("[git sparse-checkout]: {}", stdout);
-    }
+    Ok(locked_plugins
+        .into_iter()
+        .map(|(locked, _)| locked)
+        .collect())
+}
 
-    if !stderr.is_empty() {
-        error!("[git sparse-checkout]: {}", stderr);
-    }
+/// Takes an advisory lock on a directory, waiting for other flint processes holding it.
+/// The lock is released when the returned file is dropped.
+pub fn lock_dir(dir: &Path) -> Result<File> {
+    fs::create_dir_all(dir)?;
+    let file = File::create(dir.join(LOCK_FILE))?;
 
-    if !status.success() {
-        // Clean up the temporary directory if sparse-checkout fails
+    if let Err(TryLockError::WouldBlock) = file.try_lock() {
         info!(
-            "Cleaning up after failed sparse-checkout: {}",
-            temp_path.display()
+            "Waiting for another flint process to release {}",
+            dir.display()
         );
-        let _ = fs::remove_dir_all(&temp_path);
-        return app_err!("Failed to set sparse-checkout");
+        file.lock()?;
     }
 
-    // Check if the plugins directory exists
-    let temp_plugins_dir = temp_path.join("flint-plugins").join(kind_str);
-    info!(
-        "Checking for plugins directory: {}",
-        temp_plugins_dir.display()
-    );
+    Ok(file)
+}
 
-    if !temp_plugins_dir.exists() {
-        error!(
-            "Plugins directory not found: {}",
-            temp_plugins_dir.display()
-        );
+// Staging copies and backups live next to the plugin (so renames stay on one
+// filesystem) and are hidden, so they are never picked up as plugins
+fn hidden_sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}", name, suffix))
+}
 
-        fs::create_dir_all(&temp_plugins_dir)?;
-    }
+/// Replaces the plugin at `dest` with a copy of `src`. The new plugin is copied into a
+/// staging dir first and then renamed into place, so `dest` is never half written.
+fn install_plugin(src: &Path, dest: &Path) -> Result<()> {
+    let staging = hidden_sibling(dest, &format!("staging-{}", std::process::id()));
+    let backup = hidden_sibling(dest, "old");
 
-    // Copy each requested plugin to the final destination
-    for id in &plugin_ids {
-        let src_plugin_path = temp_plugins_dir.join(id);
-        let dest_plugin_path = kind_path.join(id);
-
-        if src_plugin_path.exists() {
-            // Remove the destination plugin if it already exists
-            if dest_plugin_path.exists() {
-                fs::remove_dir_all(&dest_plugin_path)?;
-            }
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    copy_dir_all(src, &staging)?;
 
-            // Copy the plugin directory recursively
-            copy_dir_all(&src_plugin_path, &dest_plugin_path)?;
-        } else {
-            warn!("Plugin not found: {}", src_plugin_path.display());
+    if dest.exists() {
+        if backup.exists() {
+            fs::remove_dir_all(&backup)?;
         }
+        fs::rename(dest, &backup)?;
     }
 
-    // Clean up the temporary directory
-    info!("Cleaning up temporary directory: {}", temp_path.display());
-    fs::remove_dir_all(&temp_path)?;
+    if let Err(err) = fs::rename(&staging, dest) {
+        // Put the old plugin back rather than leaving nothing behind
+        let _ = fs::rename(&backup, dest);
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err.into());
+    }
 
-    success!("Successfully downloaded {} plugins", kind_path.display());
+    if backup.exists() {
+        fs::remove_dir_all(&backup)?;
+    }
+    Ok(())
+}
 
-    Ok(kind_path)
+/// Cleans up after an install that crashed halfway through a swap.
+/// Must only be called while holding the plugins dir lock.
+fn recover_plugins_dir(plugins_dir: &Path) -> Result<()> {
+    for kind in ["lint", "test", "ci", "report"] {
+        let Ok(entries) = fs::read_dir(plugins_dir.join(kind)) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(hidden) = name.strip_prefix('.') else {
+                continue;
+            };
+
+            let path = entry.path();
+            match hidden.strip_suffix(".old") {
+                // The old plugin was moved aside but the new one never made it in
+                Some(id) if !path.with_file_name(id).exists() => {
+                    warn!(
+                        "Restoring {} plugin {} from an interrupted install",
+                        kind, id
+                    );
+                    fs::rename(&path, path.with_file_name(id))?;
+                }
+                _ if hidden.contains(".staging-") || hidden.ends_with(".old") => {
+                    fs::remove_dir_all(&path)?;
+                }
+                _ => (),
+            }
+        }
+    }
+    Ok(())
 }
 
 // Helper function to recursively copy directories
-fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
+pub fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
     if !dst.exists() {
         fs::create_dir_all(dst)?;
     }
@@ -185,27 +296,115 @@ fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
-// Example usage
-pub fn download_plugins(kind: PluginKind, ids: Vec<&String>) -> Result<()> {
+// Repo url, requested ref and the commit pinned in flint.lock (if any)
+type CloneKey = (String, String, Option<String>);
+
+pub fn download_plugins(
+    kind: PluginKind,
+    ids: Vec<&String>,
+    toml: &Config,
+    lockfile: &Lockfile,
+    pins: &Pins,
+    offline: bool,
+) -> Result<Vec<LockedPlugin>> {
     info!(
         "Starting download of {} {} plugins",
         ids.len(),
         kind.to_string()
     );
-    let repo_url = "https://github.com/skadewdl3/flint";
-    let config_path = get_flag!(current_dir).join("flint.toml");
-    let config = Config::load(&config_path)?;
-    let branch = config.flint.plugins_branch;
 
-    info!("Source repository: {}", repo_url);
-    info!("Branch: {}", branch);
+    // Plugins from the same repo that resolve to the same commit can share a single clone
+    let mut clones: BTreeMap<CloneKey, Vec<(&String, String)>> = BTreeMap::new();
+    let mut checksums = HashMap::new();
+    for id in ids {
+        events::emit(Event::PluginStarted {
+            plugin: id,
+            kind: &kind,
+        });
+        let default_dir = format!("flint-plugins/{}/{}", kind.to_string(), id);
+        let (repo_url, reference, dir) = match toml.plugins.sources.get(id) {
+            Some(source @ PluginSource::Path { .. }) => {
+                let path = source.local_path().unwrap_or_default();
+                let error = if path.exists() {
+                    info!("Using {} from {}", id, path.display());
+                    None
+                } else {
+                    warn!("Local plugin {} not found at {}", id, path.display());
+                    Some(format!("not found at {}", path.display()))
+                };
+                // Local plugins are used in place, there's nothing to install
+                events::emit(Event::PluginFinished {
+                    plugin: id,
+                    kind: &kind,
+                    status: if error.is_some() { "failed" } else { "local" },
+                    error: error.as_deref(),
+                });
+                continue;
+            }
+            Some(PluginSource::Git { git, rev, dir }) => (
+                git.clone(),
+                rev.clone().unwrap_or(toml.flint.plugins_branch.clone()),
+                dir.clone().unwrap_or(default_dir),
+            ),
+            None => (
+                PLUGINS_REPO.to_string(),
+                toml.flint.plugins_branch.clone(),
+                default_dir,
+            ),
+        };
+
+        let locked = lockfile.get(&kind, id).filter(|locked| {
+            locked.source == repo_url && locked.reference == reference && !pins.is_updating(id)
+        });
+        if let Some(locked) = locked {
+            checksums.insert(id.clone(), locked.checksum.clone());
+        }
+
+        clones
+            .entry((repo_url, reference, locked.map(|locked| locked.rev.clone())))
+            .or_default()
+            .push((id, dir));
+    }
+
+    let mut locked_plugins = Vec::new();
+    for ((repo_url, reference, rev), plugins) in clones {
+        info!("Source repository: {}", repo_url);
+        info!("Ref: {}", reference);
+
+        let ids = plugins
+            .iter()
+            .map(|(id, _)| id.to_string())
+            .collect::<Vec<_>>();
+        match clone_plugin_folders(
+            &repo_url,
+            kind.clone(),
+            plugins,
+            &reference,
+            rev.as_deref(),
+            &checksums,
+            offline,
+        ) {
+            Ok(locked) => locked_plugins.extend(locked),
+            Err(err) => {
+                // Nothing in the group was installed
+                for id in &ids {
+                    events::emit(Event::PluginFinished {
+                        plugin: id,
+                        kind: &kind,
+                        status: "failed",
+                        error: Some(&err.to_string()),
+                    });
+                }
+                return Err(err);
+            }
+        }
+    }
 
-    clone_plugin_folders(repo_url, kind.clone(), ids, &branch)?;
     success!("Completed downloading {} plugins", kind.to_string());
-    Ok(())
+    Ok(locked_plugins)
 }
 
-pub fn download_plugins_from_config(toml: &Config) -> Result<()> {
+pub fn download_plugins_from_config(toml: &Config, pins: &Pins, offline: bool) -> Result<()> {
     info!("Loading configuration from flint.toml");
 
     let linter_ids: Vec<&String> = toml
@@ -222,27 +421,199 @@ pub fn download_plugins_from_config(toml: &Config) -> Result<()> {
     info!("Found {} CI plugins in configuration", ci_ids.len());
     info!("Found {} report plugins in configuration", report_ids.len());
 
-    info!("Starting download of all configured plugins");
-    if tester_ids.len() > 0 {
-        download_plugins(PluginKind::Test, tester_ids)?;
-    }
-    if linter_ids.len() > 0 {
-        download_plugins(PluginKind::Lint, linter_ids)?;
-    }
-    if ci_ids.len() > 0 {
-        download_plugins(PluginKind::Ci, ci_ids)?;
+    if let Pins::Update(ids) = pins {
+        for id in ids {
+            let configured = [&linter_ids, &tester_ids, &ci_ids, &report_ids]
+                .iter()
+                .any(|ids| ids.contains(&id));
+            if !configured {
+                warn!(
+                    "Plugin {} is not configured in flint.toml, skipping update",
+                    id
+                );
+            }
+        }
     }
-    if report_ids.len() > 0 {
-        download_plugins(PluginKind::Report, report_ids)?;
+
+    // Only one install can touch the plugins dir (and flint.lock) at a time
+    let plugins_dir = get_flag!(plugins_dir);
+    let _plugins_lock = lock_dir(&plugins_dir)?;
+    recover_plugins_dir(&plugins_dir)?;
+
+    let lockfile_path = Lockfile::path();
+    let old_lockfile = Lockfile::load(&lockfile_path)?;
+
+    // Plugins that were removed from flint.toml drop out of the lockfile
+    let mut lockfile = Lockfile::default();
+
+    info!("Starting download of all configured plugins");
+    for (kind, ids) in [
+        (PluginKind::Test, tester_ids),
+        (PluginKind::Lint, linter_ids),
+        (PluginKind::Ci, ci_ids),
+        (PluginKind::Report, report_ids),
+    ] {
+        if ids.is_empty() {
+            continue;
+        }
+        for locked in download_plugins(kind, ids, toml, &old_lockfile, pins, offline)? {
+            match old_lockfile.get(&locked.kind, &locked.id) {
+                Some(old) if old.rev != locked.rev => {
+                    info!(
+                        "Updated {} from {} to {}",
+                        locked.id,
+                        short_rev(&old.rev),
+                        short_rev(&locked.rev)
+                    )
+                }
+                None => info!("Locked {} at {}", locked.id, short_rev(&locked.rev)),
+                _ => (),
+            }
+            lockfile.insert(locked);
+        }
     }
     success!("All plugins downloaded successfully");
 
+    lockfile.save(&lockfile_path)?;
+    events::emit(Event::FileWritten {
+        plugin: None,
+        path: &lockfile_path,
+    });
+    info!("Wrote {}", lockfile_path.display());
+
+    // The event stream has to stay valid JSON
+    if get_flag!(output).is_structured() {
+        return Ok(());
+    }
+
     let final_dest_path = get_flag!(plugins_dir);
     let path = Path::new(&final_dest_path);
 
     let entries = fs::read_dir(path)?;
     for entry in entries.flatten() {
+        // Like find::list, leave out the install lock and staging dirs
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
         println!("{}", entry.path().display());
     }
     Ok(())
 }
+
+fn short_rev(rev: &str) -> &str {
+    &rev[..rev.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::testing::{git, lock_flags, write_plugin};
+    use flint_utils::set_flag;
+    use tempfile::TempDir;
+
+    /// Points the plugins dir, project dir and plugin cache into `tmp`
+    fn use_dirs(tmp: &Path) -> PathBuf {
+        let project = tmp.join("project");
+        fs::create_dir_all(&project).unwrap();
+        set_flag!(plugins_dir, tmp.join("plugins"));
+        set_flag!(current_dir, project.clone());
+        set_flag!(config_path, project.join("flint.toml"));
+        std::env::set_var("FLINT_CACHE_DIR", tmp.join("cache"));
+        project
+    }
+
+    fn install(toml: &str) -> Vec<LockedPlugin> {
+        let toml: Config = toml::from_str(toml).unwrap();
+        let id = "x-lint".to_string();
+        download_plugins(
+            PluginKind::Lint,
+            vec![&id],
+            &toml,
+            &Lockfile::default(),
+            &Pins::Locked,
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn installs_git_sources_at_their_rev() {
+        let _flags = lock_flags();
+        let tmp = TempDir::new().unwrap();
+        use_dirs(tmp.path());
+
+        let work = tmp.path().join("work");
+        git(tmp.path(), &["init", "--quiet", "--bare", "origin.git"]);
+        git(tmp.path(), &["init", "--quiet", "work"]);
+        write_plugin(&work.join("plugins/x-lint"), "x-lint", "1.0.0");
+        git(&work, &["add", "-A"]);
+        git(&work, &["commit", "--quiet", "-m", "1.0.0"]);
+        git(&work, &["tag", "v1"]);
+        let first = git(&work, &["rev-parse", "HEAD"]);
+        write_plugin(&work.join("plugins/x-lint"), "x-lint", "2.0.0");
+        git(&work, &["commit", "--quiet", "-am", "2.0.0"]);
+        let url = format!("file://{}", tmp.path().join("origin.git").display());
+        git(&work, &["push", "--quiet", &url, "HEAD:main", "--tags"]);
+
+        let locked = install(&format!(
+            "[flint]\nversion = 1\n[plugins.sources]\nx-lint = {{ git = \"{}\", rev = \"v1\", dir = \"plugins/x-lint\" }}\n[rules.x-lint]\n",
+            url
+        ));
+
+        assert_eq!(locked.len(), 1);
+        assert_eq!(locked[0].source, url);
+        assert_eq!(locked[0].reference, "v1");
+        assert_eq!(locked[0].rev, first);
+        let details =
+            fs::read_to_string(tmp.path().join("plugins/lint/x-lint/details.lua")).unwrap();
+        assert!(details.contains("1.0.0"));
+    }
+
+    #[test]
+    fn rejects_locked_revs_that_are_not_commit_hashes() {
+        let _flags = lock_flags();
+        let tmp = TempDir::new().unwrap();
+        use_dirs(tmp.path());
+        let url = "file:///nonexistent.git".to_string();
+        let toml: Config = toml::from_str(&format!(
+            "[flint]\nversion = 1\n[plugins.sources]\nx-lint = {{ git = \"{}\", rev = \"main\" }}\n[rules.x-lint]\n",
+            url
+        ))
+        .unwrap();
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(LockedPlugin {
+            id: "x-lint".to_string(),
+            kind: PluginKind::Lint,
+            source: url,
+            reference: "main".to_string(),
+            rev: "--upload-pack=touch pwned".to_string(),
+            checksum: String::new(),
+        });
+
+        let id = "x-lint".to_string();
+        let result = download_plugins(
+            PluginKind::Lint,
+            vec![&id],
+            &toml,
+            &lockfile,
+            &Pins::Locked,
+            false,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn leaves_path_sources_where_they_are() {
+        let _flags = lock_flags();
+        let tmp = TempDir::new().unwrap();
+        let project = use_dirs(tmp.path());
+        write_plugin(&project.join("local/x-lint"), "x-lint", "1.0.0");
+
+        let locked = install(
+            "[flint]\nversion = 1\n[plugins.sources]\nx-lint = { path = \"local/x-lint\" }\n[rules.x-lint]\n",
+        );
+
+        assert!(locked.is_empty());
+        assert!(!tmp.path().join("plugins/lint/x-lint").exists());
+    }
+}
@@ -1,79 +1,159 @@
 use flint_ffi::add_ffi_modules;
 use flint_utils::app_err;
-use mlua::{Function, Lua, LuaSerdeExt, Value};
+use mlua::{Lua, LuaSerdeExt, Table, Value};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, process::Output, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
-use crate::{plugin::Plugin, util::toml::Config};
+use super::run::StepOutput;
+use crate::{
+    plugin::{Plugin, PluginKind},
+    util::toml::Config,
+};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TestCaseOutput {
-    file_name: String,
-    line_no: Option<u32>,
-    column_no: Option<u32>,
-    success: bool,
-    error_message: Option<String>,
-    data: Option<HashMap<String, serde_json::Value>>,
+    pub file_name: String,
+    pub line_no: Option<u32>,
+    pub column_no: Option<u32>,
+    pub success: bool,
+    pub error_message: Option<String>,
+    /// The lint rule that was broken, e.g. "no-unused-vars"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    pub data: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LintPluginEvalOutput {
-    total_errors: u32,
-    lint_results: Vec<TestCaseOutput>,
+    pub total_errors: u32,
+    pub lint_results: Vec<TestCaseOutput>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TestPluginEvalOutput {
-    tests_passed: u32,
-    total_tests: u32,
-    passing_percentage: f32,
-    test_results: Vec<TestCaseOutput>,
+    pub tests_passed: u32,
+    pub total_tests: u32,
+    pub passing_percentage: f32,
+    pub test_results: Vec<TestCaseOutput>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum PluginEvalOutput {
     Lint(LintPluginEvalOutput),
     Test(TestPluginEvalOutput),
 }
 
+impl TestCaseOutput {
+    /// The rule_id, or for plugins written before rule_id existed, `data.rule_id`
+    pub fn rule(&self) -> Option<String> {
+        self.rule_id.clone().or_else(|| {
+            self.data
+                .as_ref()
+                .and_then(|data| data.get("rule_id"))
+                .and_then(|rule_id| rule_id.as_str())
+                .map(str::to_string)
+        })
+    }
+}
+
+impl PluginEvalOutput {
+    /// A single failed result, for plugins whose command never produced output to evaluate
+    pub fn failed(kind: &PluginKind, plugin_id: &str, message: impl Into<String>) -> Self {
+        let result = TestCaseOutput {
+            file_name: plugin_id.to_string(),
+            line_no: None,
+            column_no: None,
+            success: false,
+            error_message: Some(message.into()),
+            rule_id: None,
+            data: None,
+        };
+
+        match kind {
+            PluginKind::Test => PluginEvalOutput::Test(TestPluginEvalOutput {
+                tests_passed: 0,
+                total_tests: 1,
+                passing_percentage: 0.0,
+                test_results: vec![result],
+            }),
+            _ => PluginEvalOutput::Lint(LintPluginEvalOutput {
+                total_errors: 1,
+                lint_results: vec![result],
+            }),
+        }
+    }
+}
+
+fn output_table(lua: &Lua, step: &StepOutput) -> mlua::Result<Table> {
+    let output = &step.output;
+    let table = lua.create_table()?;
+    table.set("stdout", String::from_utf8_lossy(&output.stdout))?;
+    table.set("stderr", String::from_utf8_lossy(&output.stderr))?;
+    table.set("status", output.status.code())?;
+    table.set("success", output.status.success())?;
+    table.set("truncated", step.truncated)?;
+    Ok(table)
+}
+
+/// Calls Eval() with the outputs of the steps that were run. The top level fields
+/// are those of the last step, and every step's output is under `steps.<name>`.
 pub fn eval(
     plugin: &Plugin,
-    output: Output,
+    steps: &[StepOutput],
     config: &Arc<Config>,
 ) -> flint_utils::Result<PluginEvalOutput> {
+    let Some(last_step) = steps.last() else {
+        return app_err!("{} has no command output to evaluate", plugin.details.id);
+    };
+
     let lua = Lua::new();
-    let plugin_config = plugin.get_config_lua(&lua, config);
+    let plugin_config = plugin.get_config_lua(&lua, config)?;
     add_ffi_modules(&lua)?;
 
-    let eval: Function = {
-        let contents = std::fs::read_to_string(plugin.path.join("run.lua"))?;
-
-        lua.load(contents)
-            .exec()
-            .map(|_| lua.globals().get("Eval").unwrap())
-    }?;
+    let eval = plugin.get_function(&lua, "run.lua", "Eval")?;
 
-    let evaluation_state = lua.create_table()?;
-    evaluation_state.set("stdout", String::from_utf8_lossy(&output.stdout))?;
-    evaluation_state.set("stderr", String::from_utf8_lossy(&output.stderr))?;
-    evaluation_state.set("status", output.status.code())?;
-
-    evaluation_state.set("success", output.status.success())?;
+    let evaluation_state = output_table(&lua, last_step)?;
+    let steps_table = lua.create_table()?;
+    for step in steps {
+        steps_table.set(step.name.as_str(), output_table(&lua, step)?)?;
+    }
+    evaluation_state.set("steps", steps_table)?;
 
-    let eval_output = eval.call::<mlua::Value>((evaluation_state, plugin_config))?;
+    let eval_output = eval
+        .call::<mlua::Value>((evaluation_state, plugin_config))
+        .map_err(|err| plugin.runtime_error("run.lua", err))?;
 
     let eval_output_table = match &eval_output {
         Value::Table(table) => table,
-        _ => return app_err!("Eval function should return a valid lua table"),
+        _ => {
+            return Err(plugin.contract_error(
+                "run.lua",
+                format!(
+                    "Eval() should return a table, found {}",
+                    eval_output.type_name()
+                ),
+            ))
+        }
+    };
+
+    let invalid_output = |err: mlua::Error| {
+        plugin.contract_error("run.lua", format!("invalid Eval() output: {}", err))
     };
 
     if eval_output_table.contains_key("test_results")? {
-        let test_output: TestPluginEvalOutput = lua.from_value(eval_output.clone())?;
+        let test_output: TestPluginEvalOutput = lua
+            .from_value(eval_output.clone())
+            .map_err(invalid_output)?;
         Ok(PluginEvalOutput::Test(test_output))
     } else if eval_output_table.contains_key("lint_results")? {
-        let lint_output: LintPluginEvalOutput = lua.from_value(eval_output.clone())?;
+        let lint_output: LintPluginEvalOutput = lua
+            .from_value(eval_output.clone())
+            .map_err(invalid_output)?;
         Ok(PluginEvalOutput::Lint(lint_output))
     } else {
-        app_err!("Unknown plugin output format")
+        Err(plugin.contract_error(
+            "run.lua",
+            "Eval() output should contain either test_results or lint_results",
+        ))
     }
 }
@@ -1,10 +1,13 @@
 use crate::{
-    plugin::{deps::collect_dependencies, Plugin, PluginKind},
+    plugin::{
+        deps::{collect_dependencies, Dependency},
+        Plugin, PluginKind,
+    },
     util::toml::Config,
 };
 use flint_ffi::add_ffi_modules;
-use flint_utils::{app_err, Result};
-use mlua::{Function, Lua, LuaSerdeExt};
+use flint_utils::{app_err, warn, Result};
+use mlua::{Lua, LuaSerdeExt};
 use std::{collections::HashMap, sync::Arc};
 
 pub fn collect_env_vars(
@@ -15,7 +18,16 @@ pub fn collect_env_vars(
     let lua = Lua::new();
 
     for plugin in active_plugins {
-        let plugin_config = plugin.get_config_lua(&lua, toml);
+        let plugin_config = match plugin.get_config_lua(&lua, toml) {
+            Ok(plugin_config) => plugin_config,
+            Err(err) => {
+                warn!(
+                    "Skipping env vars for plugin {}: {}",
+                    plugin.details.id, err
+                );
+                continue;
+            }
+        };
         if let Ok(env) = plugin_config.get::<HashMap<String, String>>("env") {
             for (key, value) in env {
                 env_vars.insert(key, value);
@@ -26,56 +38,82 @@ pub fn collect_env_vars(
     env_vars
 }
 
+/// What a CI plugin's Generate() is called with on top of its config
+pub struct CiInputs {
+    pub dependencies: HashMap<String, Vec<Dependency>>,
+    pub env: HashMap<String, String>,
+}
+
 pub fn generate<'a>(plugin: &Plugin, toml: &Arc<Config>) -> Result<HashMap<String, String>> {
-    let lua = Lua::new();
-    add_ffi_modules(&lua)?;
+    if plugin.kind != PluginKind::Ci {
+        return generate_with(plugin, toml, None);
+    }
 
-    let plugin_config = plugin.get_config_lua(&lua, toml);
+    let active_plugins = crate::plugin::list_from_config(&toml)?;
 
-    let generate: Function = {
-        let contents = std::fs::read_to_string(plugin.path.join("generate.lua"))?;
+    // Filter out CI plugins from active_plugins to avoid circular dependencies
+    let active_plugins = active_plugins
+        .into_iter()
+        .filter(|p| p.kind != PluginKind::Ci)
+        .collect::<Vec<_>>();
 
-        lua.load(contents)
-            .exec()
-            .map(|_| lua.globals().get("Generate").unwrap())
-    }?;
+    let ci_inputs = CiInputs {
+        dependencies: collect_dependencies(&active_plugins)?,
+        env: collect_env_vars(&toml.clone(), &active_plugins),
+    };
+    generate_with(plugin, toml, Some(&ci_inputs))
+}
 
-    let validate: Function = {
-        let contents = std::fs::read_to_string(plugin.path.join("validate.lua"))?;
-        lua.load(contents)
-            .exec()
-            .map(|_| lua.globals().get("Validate").unwrap())
-    }?;
+/// Validates the plugin's config and calls Generate(). CI plugins need `ci_inputs`,
+/// which `generate` collects from the other configured plugins.
+pub fn generate_with(
+    plugin: &Plugin,
+    toml: &Arc<Config>,
+    ci_inputs: Option<&CiInputs>,
+) -> Result<HashMap<String, String>> {
+    let lua = Lua::new();
+    add_ffi_modules(&lua)?;
 
-    let validate_success = validate.call::<mlua::Value>(&plugin_config)?;
+    let plugin_config = plugin.get_config_lua(&lua, toml)?;
 
-    let validate_success: bool = lua.from_value(validate_success)?;
+    let generate = plugin.get_function(&lua, "generate.lua", "Generate")?;
+    let validate = plugin.get_function(&lua, "validate.lua", "Validate")?;
+
+    let validate_success = validate
+        .call::<mlua::Value>(&plugin_config)
+        .map_err(|err| plugin.runtime_error("validate.lua", err))?;
+
+    let validate_success: bool = lua.from_value(validate_success).map_err(|err| {
+        plugin.contract_error(
+            "validate.lua",
+            format!("Validate() should return a boolean: {}", err),
+        )
+    })?;
 
     if !validate_success {
         return app_err!("Plugin configuration validation failed");
     }
 
-    let generate_results = if plugin.kind == PluginKind::Ci {
-        let active_plugins = crate::plugin::list_from_config(&toml);
-
-        // Filter out CI plugins from active_plugins to avoid circular dependencies
-        let active_plugins = active_plugins
-            .into_iter()
-            .filter(|p| p.kind != PluginKind::Ci)
-            .collect::<Vec<_>>();
-
-        let dependencies = collect_dependencies(&active_plugins)?;
-        let env = collect_env_vars(&toml.clone(), &active_plugins);
-        let env_table = lua.to_value(&env)?;
-
-        let deps_table = lua.to_value(&dependencies)?;
-
-        generate.call::<mlua::Value>((plugin_config, deps_table, env_table))
-    } else {
-        generate.call::<mlua::Value>(plugin_config)
-    }?;
-
-    let generate_results: HashMap<String, String> = lua.from_value(generate_results)?;
+    let generate_results = match ci_inputs {
+        Some(ci_inputs) => {
+            let deps_table = lua.to_value(&ci_inputs.dependencies)?;
+            let env_table = lua.to_value(&ci_inputs.env)?;
+            generate.call::<mlua::Value>((plugin_config, deps_table, env_table))
+        }
+        None => generate.call::<mlua::Value>(plugin_config),
+    }
+    .map_err(|err| plugin.runtime_error("generate.lua", err))?;
+
+    let generate_results: HashMap<String, String> =
+        lua.from_value(generate_results).map_err(|err| {
+            plugin.contract_error(
+                "generate.lua",
+                format!(
+                    "Generate() should return a table of file names to contents: {}",
+                    err
+                ),
+            )
+        })?;
 
     Ok(generate_results)
 }
@@ -1,11 +1,11 @@
 use crate::{
-    plugin::{Plugin, PluginKind},
+    plugin::{format, Plugin, PluginKind},
     util::toml::Config,
 };
 use flint_ffi::add_ffi_modules;
 use flint_utils::app_err;
 use flint_utils::Result;
-use mlua::{Function, Lua, LuaSerdeExt, Value};
+use mlua::{Lua, LuaSerdeExt, Value};
 use std::{collections::HashMap, sync::Arc};
 
 use super::eval::PluginEvalOutput;
@@ -15,6 +15,7 @@ pub fn report(
     toml: &Arc<Config>,
     output: &PluginEvalOutput,
     plugin_id: &str,
+    cached: bool,
 ) -> Result<HashMap<String, String>> {
     if plugin.kind != PluginKind::Report {
         return app_err!("{} is not a reporting plugin.", plugin.details.id);
@@ -22,31 +23,38 @@ pub fn report(
 
     let lua = Lua::new();
     add_ffi_modules(&lua)?;
+    format::add_lua_modules(&lua)?;
 
-    let plugin_config = plugin.get_config_lua(&lua, toml);
+    let plugin_config = plugin.get_config_lua(&lua, toml)?;
 
-    let report: Function = {
-        let contents = std::fs::read_to_string(plugin.path.join("run.lua"))
-            .expect("Error reading plugin code");
-
-        lua.load(contents)
-            .exec()
-            .map(|_| lua.globals().get("Run").unwrap())
-    }?;
+    let report = plugin.get_function(&lua, "run.lua", "Run")?;
 
     let report_state = lua.create_table()?;
     report_state.set("config", plugin_config)?;
     let output_lua = lua.to_value(&output)?;
     report_state.set("output", output_lua)?;
     report_state.set("plugin_id", plugin_id)?;
+    // Set when the result was replayed from .flint/cache instead of running the plugin
+    report_state.set("cached", cached)?;
 
-    let report_results = report.call::<mlua::Value>(report_state)?;
+    let report_results = report
+        .call::<mlua::Value>(report_state)
+        .map_err(|err| plugin.runtime_error("run.lua", err))?;
 
     if let Value::Nil = report_results {
         return Ok(HashMap::new());
     }
 
-    let report_results: HashMap<String, String> = lua.from_value(report_results)?;
+    let report_results: HashMap<String, String> =
+        lua.from_value(report_results).map_err(|err| {
+            plugin.contract_error(
+                "run.lua",
+                format!(
+                    "Run() should return a table of file names to contents: {}",
+                    err
+                ),
+            )
+        })?;
 
     Ok(report_results)
 }
@@ -1,26 +1,239 @@
 use crate::{plugin::Plugin, util::toml::Config};
 use flint_ffi::add_ffi_modules;
 use flint_utils::Result;
-use mlua::{Function, Lua, LuaSerdeExt};
-use std::sync::Arc;
+use mlua::{Lua, LuaSerdeExt, Value};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, process::Output, sync::Arc, time::Duration};
 
-pub fn run<'a>(plugin: &Plugin, toml: &Arc<Config>) -> Result<Vec<String>> {
+/// The command a lint or test plugin wants flint to run. `Run()` returns either
+/// a list of strings (the program and its arguments) or a table with these fields.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RunCommand {
+    pub cmd: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables, on top of the ones flint was started with
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Directory to run in, relative to the directory containing flint.toml
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Written to the command's stdin, which is empty otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    /// Seconds the command may run for, used unless flint.toml sets a timeout for the plugin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f64>,
+}
+
+impl RunCommand {
+    /// Whether the command could have been returned as a plain list of strings
+    pub fn is_argv(&self) -> bool {
+        self.env.is_empty() && self.cwd.is_none() && self.stdin.is_none() && self.timeout.is_none()
+    }
+
+    pub fn argv(&self) -> Vec<String> {
+        std::iter::once(self.cmd.clone())
+            .chain(self.args.iter().cloned())
+            .collect()
+    }
+}
+
+impl fmt::Display for RunCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.argv().join(" "))?;
+        if let Some(cwd) = &self.cwd {
+            write!(f, " (in {})", cwd)?;
+        }
+        Ok(())
+    }
+}
+
+/// One command of a multi-step run, e.g. `{ name = "install", cmd = "npm", args = { "ci" } }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStep {
+    /// Eval() finds the step's output under this name
+    pub name: String,
+    /// Carry on with the next step if this one exits with an error
+    pub continue_on_failure: bool,
+    pub command: RunCommand,
+}
+
+/// Everything Run() asked for, as an ordered list of steps
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunPlan {
+    pub steps: Vec<RunStep>,
+}
+
+impl RunPlan {
+    /// The name of the only step, when Run() returns a single command
+    pub const SINGLE_STEP: &'static str = "run";
+
+    pub fn single(command: RunCommand) -> Self {
+        Self {
+            steps: vec![RunStep {
+                name: Self::SINGLE_STEP.to_string(),
+                continue_on_failure: false,
+                command,
+            }],
+        }
+    }
+
+    /// Whether Run() returned a single command rather than a list of steps
+    pub fn is_single(&self) -> bool {
+        self.steps.len() == 1 && self.steps[0].name == Self::SINGLE_STEP
+    }
+}
+
+impl fmt::Display for RunPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_single() {
+            return write!(f, "{}", self.steps[0].command);
+        }
+
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| format!("{}: {}", step.name, step.command))
+            .collect::<Vec<_>>();
+        write!(f, "{}", steps.join(", then "))
+    }
+}
+
+/// The output of a step that was run
+#[derive(Debug, Clone)]
+pub struct StepOutput {
+    pub name: String,
+    pub output: Output,
+    /// The command exited but left its output open, so not all of it was read
+    pub truncated: bool,
+}
+
+pub fn run<'a>(plugin: &Plugin, toml: &Arc<Config>, files: Option<&[String]>) -> Result<RunPlan> {
     let lua = Lua::new();
     add_ffi_modules(&lua)?;
-    let plugin_config = plugin.get_config_lua(&lua, toml);
+    let plugin_config = plugin.get_config_lua(&lua, toml)?;
+    // Left unset when the whole project is checked
+    if let Some(files) = files {
+        plugin_config.set("files", lua.to_value(files)?)?;
+    }
+
+    let run = plugin.get_function(&lua, "run.lua", "Run")?;
+
+    let run_success = run
+        .call::<mlua::Value>(plugin_config)
+        .map_err(|err| plugin.runtime_error("run.lua", err))?;
+
+    let steps = match &run_success {
+        Value::Table(table) if table.contains_key("steps")? => table.get::<Value>("steps")?,
+        _ => return Ok(RunPlan::single(parse_command(plugin, &lua, run_success)?)),
+    };
+
+    let Value::Table(steps) = steps else {
+        return Err(plugin.contract_error(
+            "run.lua",
+            format!("Run() steps should be a list, found {}", steps.type_name()),
+        ));
+    };
+
+    let mut plan = RunPlan { steps: Vec::new() };
+    for step in steps.sequence_values::<Value>() {
+        let step = parse_step(plugin, &lua, step?)?;
+        if plan.steps.iter().any(|other| other.name == step.name) {
+            return Err(plugin.contract_error(
+                "run.lua",
+                format!("Run() has more than one step called {}", step.name),
+            ));
+        }
+        plan.steps.push(step);
+    }
+
+    if plan.steps.is_empty() {
+        return Err(plugin.contract_error("run.lua", "Run() returned no steps"));
+    }
+    Ok(plan)
+}
+
+fn parse_step(plugin: &Plugin, lua: &Lua, step: Value) -> Result<RunStep> {
+    let Value::Table(table) = &step else {
+        return Err(plugin.contract_error(
+            "run.lua",
+            format!("Run() steps should be tables, found {}", step.type_name()),
+        ));
+    };
+
+    let name = match table.get::<Value>("name")? {
+        Value::String(name) => name.to_str()?.to_string(),
+        _ => {
+            return Err(
+                plugin.contract_error("run.lua", "every step returned by Run() needs a name")
+            )
+        }
+    };
+    let continue_on_failure = table
+        .get::<Option<bool>>("continue_on_failure")
+        .map_err(|err| {
+            plugin.contract_error(
+                "run.lua",
+                format!("continue_on_failure of step {}: {}", name, err),
+            )
+        })?
+        .unwrap_or(false);
+
+    // The rest of the table is an ordinary command
+    table.set("name", Value::Nil)?;
+    table.set("continue_on_failure", Value::Nil)?;
+    let command = parse_command(plugin, lua, step)?;
+
+    Ok(RunStep {
+        name,
+        continue_on_failure,
+        command,
+    })
+}
 
-    let run: Function = {
-        let contents = std::fs::read_to_string(plugin.path.join("run.lua"))
-            .expect("Error reading plugin code");
+fn parse_command(plugin: &Plugin, lua: &Lua, value: Value) -> Result<RunCommand> {
+    // Lists are argv, tables with a cmd key spell the command out
+    let is_table_form = match &value {
+        Value::Table(table) => table.contains_key("cmd")?,
+        _ => false,
+    };
 
-        lua.load(contents)
-            .exec()
-            .map(|_| lua.globals().get("Run").unwrap())
-    }?;
+    let command = if is_table_form {
+        lua.from_value::<RunCommand>(value).map_err(|err| {
+            plugin.contract_error("run.lua", format!("invalid Run() command table: {}", err))
+        })?
+    } else {
+        let argv: Vec<String> = lua.from_value(value).map_err(|err| {
+            plugin.contract_error(
+                "run.lua",
+                format!(
+                    "Run() should return a list of strings, a table with a cmd field or a table with steps: {}",
+                    err
+                ),
+            )
+        })?;
 
-    let run_success = run.call::<mlua::Value>(plugin_config)?;
+        let mut argv = argv.into_iter();
+        RunCommand {
+            cmd: argv.next().unwrap_or_default(),
+            args: argv.collect(),
+            ..Default::default()
+        }
+    };
 
-    let run_command: Vec<String> = lua.from_value(run_success)?;
+    if command.cmd.is_empty() {
+        return Err(plugin.contract_error("run.lua", "Run() returned an empty command"));
+    }
+    if let Some(timeout) = command.timeout {
+        if Duration::try_from_secs_f64(timeout).is_err() {
+            return Err(plugin.contract_error(
+                "run.lua",
+                format!("Run() returned an invalid timeout: {}", timeout),
+            ));
+        }
+    }
 
-    Ok(run_command)
+    Ok(command)
 }
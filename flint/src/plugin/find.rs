@@ -1,10 +1,12 @@
 use super::validate::validate_plugin_structure;
-use super::{Plugin, PluginDetails, PluginKind};
+use super::version::check_compatibility;
+use super::{lua_traceback, Plugin, PluginDetails, PluginKind};
 use crate::util::toml::Config;
-use flint_utils::{debug, error, get_flag, Result};
-use mlua::{Function, Lua, LuaSerdeExt};
+use flint_utils::{debug, error, get_flag, warn, Error, Result};
+use mlua::{Lua, LuaSerdeExt, Value};
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
     sync::OnceLock,
 };
 
@@ -26,6 +28,28 @@ pub fn map() -> &'static HashMap<String, BTreeSet<Plugin>> {
     })
 }
 
+/// Groups files by the plugins that handle them, going by the plugins' `extensions`.
+/// Plugins that don't handle any of the files are left out.
+pub fn files_by_plugin(files: &[String]) -> HashMap<(PluginKind, String), Vec<String>> {
+    let mut by_plugin = HashMap::new();
+    for file in files {
+        let Some(extension) = Path::new(file).extension() else {
+            continue;
+        };
+        let Some(plugins) = map().get(extension.to_string_lossy().as_ref()) else {
+            continue;
+        };
+
+        for plugin in plugins {
+            by_plugin
+                .entry((plugin.kind.clone(), plugin.details.id.clone()))
+                .or_insert_with(Vec::new)
+                .push(file.clone());
+        }
+    }
+    by_plugin
+}
+
 pub fn list<'a>() -> Result<&'a BTreeSet<Plugin>> {
     let lua = Lua::new();
     flint_ffi::add_ffi_modules(&lua)?;
@@ -65,47 +89,34 @@ pub fn list<'a>() -> Result<&'a BTreeSet<Plugin>> {
                         }
                     };
 
-                    let path = entry.path();
-                    let contents = match std::fs::read_to_string(&path.join("details.lua")) {
-                        Ok(contents) => contents,
+                    // Hidden entries are lock files and staging dirs from `flint install`
+                    if entry.file_name().to_string_lossy().starts_with('.') {
+                        return None;
+                    }
+
+                    let kind = match *dir_name {
+                        "test" => PluginKind::Test,
+                        "lint" => PluginKind::Lint,
+                        "ci" => PluginKind::Ci,
+                        "report" => PluginKind::Report,
+                        _ => unreachable!(),
+                    };
+
+                    let plugin = match load_plugin(&lua, entry.path(), kind) {
+                        Ok(plugin) => plugin,
                         Err(err) => {
-                            error!("Error reading file {}: {}", path.display(), err);
+                            error!("{}", err);
                             return None;
                         }
                     };
 
-                    match lua.load(contents).exec() {
-                        Ok(_) => {
-                            let details: Function = lua.globals().get("Details").unwrap();
-                            let lua_val = details.call::<mlua::Value>(()).unwrap();
-                            let details: PluginDetails = lua.from_value(lua_val).unwrap();
-
-                            let plugin = Plugin {
-                                details,
-                                path,
-                                kind: match *dir_name {
-                                    "test" => PluginKind::Test,
-                                    "lint" => PluginKind::Lint,
-                                    "ci" => PluginKind::Ci,
-                                    "report" => PluginKind::Report,
-                                    _ => unreachable!(),
-                                },
-                            };
-
-                            match validate_plugin_structure(&plugin) {
-                                Ok(_) => Some(plugin),
-                                Err(err) => {
-                                    error!(
-                                        "Plugin {} has invalid file structure.\nError message: {}",
-                                        plugin.details.id, err
-                                    );
-                                    None
-                                }
-                            }
-                        }
-
+                    match validate_plugin_structure(&plugin) {
+                        Ok(_) => Some(plugin),
                         Err(err) => {
-                            error!("Error loading lua file {}: {}", path.display(), err);
+                            error!(
+                                "Plugin {} has invalid file structure.\nError message: {}",
+                                plugin.details.id, err
+                            );
                             None
                         }
                     }
@@ -113,16 +124,149 @@ pub fn list<'a>() -> Result<&'a BTreeSet<Plugin>> {
                 .collect::<Vec<_>>()
         });
 
-    let x = PLUGINS.get_or_init(|| plugins.collect::<BTreeSet<Plugin>>());
+    let mut plugins = plugins.collect::<Vec<Plugin>>();
+
+    // Plugins with a local path source are used in place, and take precedence over installed ones
+    for plugin in list_local(&lua) {
+        plugins
+            .retain(|other| !(other.kind == plugin.kind && other.details.id == plugin.details.id));
+        plugins.push(plugin);
+    }
+
+    let x = PLUGINS.get_or_init(|| plugins.into_iter().collect::<BTreeSet<Plugin>>());
     Ok(x)
 }
 
+/// Loads the plugins that flint.toml points at a local directory via `[plugins.sources]`
+fn list_local(lua: &Lua) -> Vec<Plugin> {
+    let config_path = get_flag!(config_path);
+    let Ok(config) = Config::load(&config_path) else {
+        return vec![];
+    };
+
+    config
+        .plugins
+        .sources
+        .iter()
+        .filter_map(|(id, source)| {
+            let path = source.local_path()?;
+
+            // A local plugin's kind is decided by the section it is configured in
+            let kind = if config.rules.contains_key(id) {
+                PluginKind::Lint
+            } else if config.tests.contains_key(id) {
+                PluginKind::Test
+            } else if config.ci.contains_key(id) {
+                PluginKind::Ci
+            } else if config.report.contains_key(id) {
+                PluginKind::Report
+            } else {
+                warn!(
+                    "Plugin {} has a source but is not configured in flint.toml",
+                    id
+                );
+                return None;
+            };
+
+            let plugin = match load_plugin(lua, path, kind) {
+                Ok(plugin) => plugin,
+                Err(err) => {
+                    error!("{}", err);
+                    return None;
+                }
+            };
+
+            if &plugin.details.id != id {
+                warn!(
+                    "Local plugin at {} has id {}, expected {}",
+                    plugin.path.display(),
+                    plugin.details.id,
+                    id
+                );
+            }
+
+            match validate_plugin_structure(&plugin) {
+                Ok(_) => Some(plugin),
+                Err(err) => {
+                    error!(
+                        "Plugin {} has invalid file structure.\nError message: {}",
+                        plugin.details.id, err
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Reads a plugin's details.lua, before we know its id the directory name is used in errors
+pub fn load_plugin(lua: &Lua, path: PathBuf, kind: PluginKind) -> Result<Plugin> {
+    let dir_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file = path.join("details.lua");
+
+    let contents = std::fs::read_to_string(&file).map_err(|err| Error::PluginLoad {
+        plugin_id: dir_name.clone(),
+        file: file.clone(),
+        traceback: err.to_string(),
+    })?;
+
+    // Globals are shared between plugins, so don't pick up a Details() left behind by another one
+    lua.globals().set("Details", Value::Nil)?;
+    lua.load(contents)
+        .set_name(format!("@{}", file.display()))
+        .exec()
+        .map_err(|err| Error::PluginLoad {
+            plugin_id: dir_name.clone(),
+            file: file.clone(),
+            traceback: lua_traceback(&err),
+        })?;
+
+    let contract_error = |message: String| Error::PluginContract {
+        plugin_id: dir_name.clone(),
+        file: file.clone(),
+        message,
+    };
+
+    let details = match lua.globals().get::<Value>("Details")? {
+        Value::Function(details) => details,
+        _ => return Err(contract_error("Details() is not defined".to_string())),
+    };
+
+    let lua_val = details
+        .call::<Value>(())
+        .map_err(|err| Error::PluginRuntime {
+            plugin_id: dir_name.clone(),
+            file: file.clone(),
+            traceback: lua_traceback(&err),
+        })?;
+    let details: PluginDetails = lua
+        .from_value(lua_val)
+        .map_err(|err| contract_error(format!("invalid Details() output: {}", err)))?;
+
+    check_compatibility(&details, &file)?;
+
+    Ok(Plugin {
+        details,
+        path,
+        kind,
+    })
+}
+
 pub fn list_from_config<'a>(config: &Config) -> Vec<&'a Plugin> {
     let linter_ids = config.rules.keys().collect::<HashSet<&String>>();
     let tester_ids = config.tests.keys().collect::<HashSet<&String>>();
     let ci_ids = config.ci.keys().collect::<HashSet<&String>>();
     let report_ids = config.report.keys().collect::<HashSet<&String>>();
-    let plugins = list().unwrap();
+    let plugins = match list() {
+        Ok(plugins) => plugins,
+        Err(err) => {
+            error!("Failed to load plugins: {}", err);
+            return Vec::new();
+        }
+    };
     debug!("Loaded plugins: {:?}", plugins);
 
     plugins
@@ -135,3 +279,51 @@ pub fn list_from_config<'a>(config: &Config) -> Vec<&'a Plugin> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::testing::{lock_flags, write_plugin};
+    use flint_utils::set_flag;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn loads_path_sources_relative_to_the_project() {
+        let _flags = lock_flags();
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().to_path_buf();
+        set_flag!(current_dir, project.clone());
+        set_flag!(config_path, project.join("flint.toml"));
+        fs::write(
+            project.join("flint.toml"),
+            "[flint]\nversion = 1\n[plugins.sources]\nx-lint = { path = \"local/x-lint\" }\ny-test = { path = \"local/y-test\" }\nunused = { path = \"local/unused\" }\n[rules.x-lint]\n[tests.y-test]\n",
+        )
+        .unwrap();
+        write_plugin(&project.join("local/x-lint"), "x-lint", "1.0.0");
+        write_plugin(&project.join("local/y-test"), "y-test", "1.0.0");
+        write_plugin(&project.join("local/unused"), "unused", "1.0.0");
+
+        let mut plugins = list_local(&Lua::new())
+            .into_iter()
+            .map(|plugin| (plugin.kind, plugin.details.id, plugin.path))
+            .collect::<Vec<_>>();
+        plugins.sort();
+
+        assert_eq!(
+            plugins,
+            vec![
+                (
+                    PluginKind::Lint,
+                    "x-lint".to_string(),
+                    project.join("local/x-lint")
+                ),
+                (
+                    PluginKind::Test,
+                    "y-test".to_string(),
+                    project.join("local/y-test")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use flint_utils::{Error, Result};
+use std::{collections::BTreeMap, fmt::Write};
+
+use super::PluginResults;
+use crate::plugin::exec::eval::{PluginEvalOutput, TestCaseOutput};
+
+/// JUnit XML with one testsuite per plugin. Test results become one testcase each,
+/// lint results one testcase per file, failing with every issue in the file. A plugin
+/// that couldn't run, or timed out, is a single testcase with the error.
+pub fn render(results: &[PluginResults]) -> Result<String> {
+    let suites = results.iter().map(suite).collect::<Vec<_>>();
+    let mut xml = String::new();
+    write_xml(&suites, &mut xml).map_err(|err| Error::Err(err.to_string()))?;
+    Ok(xml)
+}
+
+fn write_xml(suites: &[Suite], xml: &mut String) -> std::fmt::Result {
+    writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        xml,
+        "<testsuites name=\"flint\" tests=\"{}\" failures=\"{}\" errors=\"{}\">",
+        suites.iter().map(|suite| suite.cases.len()).sum::<usize>(),
+        suites.iter().map(Suite::failures).sum::<usize>(),
+        suites.iter().map(Suite::errors).sum::<usize>(),
+    )?;
+    for suite in suites {
+        suite.write(xml)?;
+    }
+    writeln!(xml, "</testsuites>")
+}
+
+/// A testcase and why it failed (a message and the details), if it did
+struct Case {
+    name: String,
+    /// Only lint results are known to point at a file, test plugins often put the test name there
+    file: Option<String>,
+    failure: Option<(String, String)>,
+    /// Why the plugin couldn't run
+    error: Option<String>,
+}
+
+struct Suite<'a> {
+    plugin_id: &'a str,
+    cases: Vec<Case>,
+}
+
+impl Suite<'_> {
+    fn failures(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| case.failure.is_some())
+            .count()
+    }
+
+    fn errors(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| case.error.is_some())
+            .count()
+    }
+
+    fn write(&self, xml: &mut String) -> std::fmt::Result {
+        writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">",
+            escape(self.plugin_id),
+            self.cases.len(),
+            self.failures(),
+            self.errors(),
+        )?;
+        for case in &self.cases {
+            write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape(&case.name),
+                escape(self.plugin_id),
+            )?;
+            if let Some(file) = &case.file {
+                write!(xml, " file=\"{}\"", escape(file))?;
+            }
+            if case.failure.is_none() && case.error.is_none() {
+                writeln!(xml, "/>")?;
+                continue;
+            }
+            writeln!(xml, ">")?;
+            if let Some((message, details)) = &case.failure {
+                writeln!(
+                    xml,
+                    "      <failure message=\"{}\">{}</failure>",
+                    escape(message),
+                    escape(details),
+                )?;
+            }
+            if let Some(error) = &case.error {
+                // Errors can be whole Lua tracebacks, which don't fit in an attribute
+                writeln!(
+                    xml,
+                    "      <error message=\"{}\">{}</error>",
+                    escape(error.lines().next().unwrap_or_default()),
+                    escape(error),
+                )?;
+            }
+            writeln!(xml, "    </testcase>")?;
+        }
+        writeln!(xml, "  </testsuite>")
+    }
+}
+
+fn suite<'a>(plugin: &PluginResults<'a>) -> Suite<'a> {
+    // A testcase of its own, so CI servers show it even when the plugin has no results
+    let cases = match (plugin.error, plugin.output) {
+        (Some(error), _) => vec![Case {
+            name: plugin.plugin_id.to_string(),
+            file: None,
+            failure: None,
+            error: Some(error.to_string()),
+        }],
+        (None, Some(PluginEvalOutput::Lint(output))) => lint_cases(&output.lint_results),
+        (None, Some(PluginEvalOutput::Test(output))) => {
+            output.test_results.iter().map(test_case).collect()
+        }
+        (None, None) => Vec::new(),
+    };
+    Suite {
+        plugin_id: plugin.plugin_id,
+        cases,
+    }
+}
+
+fn test_case(result: &TestCaseOutput) -> Case {
+    let message = message(result);
+    let details = match result.line_no {
+        Some(_) => format!("{} {}", location(result), message),
+        None => message.clone(),
+    };
+    Case {
+        name: result.file_name.clone(),
+        file: None,
+        failure: (!result.success).then_some((message, details)),
+        error: None,
+    }
+}
+
+/// One testcase per file the plugin reported on, failing with its issues. Files the
+/// plugin has no results for don't get one, lint plugins rarely report clean files.
+fn lint_cases(results: &[TestCaseOutput]) -> Vec<Case> {
+    let mut by_file = BTreeMap::<&str, Vec<&TestCaseOutput>>::new();
+    for result in results {
+        by_file.entry(&result.file_name).or_default().push(result);
+    }
+
+    by_file
+        .into_iter()
+        .map(|(file, results)| {
+            let issues = results
+                .iter()
+                .filter(|result| !result.success)
+                .collect::<Vec<_>>();
+            let failure = (!issues.is_empty()).then(|| {
+                let details = issues
+                    .iter()
+                    .map(|result| {
+                        let rule = result
+                            .rule()
+                            .map(|rule| format!(" [{}]", rule))
+                            .unwrap_or_default();
+                        format!("{} {}{}", location(result), message(result), rule)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let summary = match issues.len() {
+                    1 => "1 issue".to_string(),
+                    count => format!("{} issues", count),
+                };
+                (summary, details)
+            });
+            Case {
+                name: file.to_string(),
+                file: Some(file.to_string()),
+                failure,
+                error: None,
+            }
+        })
+        .collect()
+}
+
+fn message(result: &TestCaseOutput) -> String {
+    result
+        .error_message
+        .clone()
+        .unwrap_or_else(|| "Failed".to_string())
+}
+
+/// file:line:column, leaving out what the plugin didn't know
+fn location(result: &TestCaseOutput) -> String {
+    let mut location = result.file_name.clone();
+    if let Some(line) = result.line_no {
+        location += &format!(":{}", line);
+        if let Some(column) = result.column_no {
+            location += &format!(":{}", column);
+        }
+    }
+    location
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Not allowed in XML 1.0 at all
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(file_name: &str, line_no: u32, success: bool) -> TestCaseOutput {
+        TestCaseOutput {
+            file_name: file_name.to_string(),
+            line_no: Some(line_no),
+            column_no: None,
+            success,
+            error_message: (!success).then(|| "Unexpected var".to_string()),
+            rule_id: Some("no-var".to_string()),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn groups_lint_results_by_file() {
+        let cases = lint_cases(&[
+            result("b.js", 1, false),
+            result("a.js", 4, true),
+            result("b.js", 9, false),
+        ]);
+
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].name, "a.js");
+        assert!(cases[0].failure.is_none());
+        assert_eq!(cases[1].name, "b.js");
+        let (message, details) = cases[1].failure.as_ref().unwrap();
+        assert_eq!(message, "2 issues");
+        assert_eq!(
+            details,
+            "b.js:1 Unexpected var [no-var]\nb.js:9 Unexpected var [no-var]"
+        );
+    }
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn drops_control_characters_but_keeps_whitespace() {
+        assert_eq!(
+            escape("\u{1b}[31mred\u{1b}[0m\n\tdone\r"),
+            "[31mred[0m\n\tdone\r"
+        );
+        assert_eq!(escape("plain text"), "plain text");
+    }
+}
@@ -0,0 +1,79 @@
+use clap::ValueEnum;
+use flint_utils::Result;
+use mlua::{Lua, LuaSerdeExt, Table, Value};
+
+use super::exec::eval::PluginEvalOutput;
+
+pub mod junit;
+pub mod sarif;
+
+/// Formats `flint test --format` can write the results in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ResultFormat {
+    Sarif,
+    Junit,
+}
+
+impl ResultFormat {
+    /// Where the results are written unless `--output-file` says otherwise
+    pub fn default_file(&self) -> &'static str {
+        match self {
+            ResultFormat::Sarif => "flint-results.sarif",
+            ResultFormat::Junit => "flint-results.xml",
+        }
+    }
+
+    /// The module report plugins `require` to use the format
+    pub fn lua_module(&self) -> &'static str {
+        match self {
+            ResultFormat::Sarif => "sarif",
+            ResultFormat::Junit => "junit",
+        }
+    }
+
+    pub fn render(&self, results: &[PluginResults]) -> Result<String> {
+        match self {
+            ResultFormat::Sarif => sarif::render(results),
+            ResultFormat::Junit => junit::render(results),
+        }
+    }
+}
+
+/// The results of one lint or test plugin
+#[derive(Debug, Clone, Copy)]
+pub struct PluginResults<'a> {
+    pub plugin_id: &'a str,
+    pub version: Option<&'a str>,
+    /// None when the plugin couldn't be run
+    pub output: Option<&'a PluginEvalOutput>,
+    /// Why the plugin failed to run or timed out
+    pub error: Option<&'a str>,
+}
+
+/// Registers a module per format for report plugins, e.g.
+/// `require("sarif").from_output(options.plugin_id, options.output)`
+pub fn add_lua_modules(lua: &Lua) -> Result<()> {
+    let loaded: Table = lua.globals().get::<Table>("package")?.get("loaded")?;
+
+    for &format in ResultFormat::value_variants() {
+        let module = lua.create_table()?;
+        module.set(
+            "from_output",
+            lua.create_function(move |lua, (plugin_id, output): (String, Value)| {
+                let output: PluginEvalOutput = lua.from_value(output)?;
+                let results = PluginResults {
+                    plugin_id: &plugin_id,
+                    version: None,
+                    output: Some(&output),
+                    error: None,
+                };
+                format
+                    .render(&[results])
+                    .map_err(|err| mlua::Error::external(err.to_string()))
+            })?,
+        )?;
+        loaded.set(format.lua_module(), module)?;
+    }
+
+    Ok(())
+}
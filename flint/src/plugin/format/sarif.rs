@@ -0,0 +1,214 @@
+use flint_utils::Result;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+
+use super::PluginResults;
+use crate::plugin::exec::eval::{PluginEvalOutput, TestCaseOutput};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const VERSION: &str = "2.1.0";
+
+/// A SARIF 2.1.0 log with one run per plugin. Only failed results are included.
+pub fn render(results: &[PluginResults]) -> Result<String> {
+    let log = json!({
+        "$schema": SCHEMA,
+        "version": VERSION,
+        "runs": results.iter().map(run).collect::<Vec<_>>(),
+    });
+    Ok(serde_json::to_string_pretty(&log)? + "\n")
+}
+
+fn run(plugin: &PluginResults) -> Value {
+    let cases: &[TestCaseOutput] = match plugin.output {
+        Some(PluginEvalOutput::Lint(output)) => &output.lint_results,
+        Some(PluginEvalOutput::Test(output)) => &output.test_results,
+        None => &[],
+    };
+    let failed = cases.iter().filter(|case| !case.success);
+
+    let rules = failed
+        .clone()
+        .filter_map(TestCaseOutput::rule)
+        .collect::<BTreeSet<_>>();
+
+    let mut driver = Map::new();
+    driver.insert("name".into(), plugin.plugin_id.into());
+    if let Some(version) = plugin.version {
+        driver.insert("version".into(), version.into());
+    }
+    driver.insert(
+        "rules".into(),
+        rules.iter().map(|id| json!({ "id": id })).collect(),
+    );
+
+    let mut run = Map::new();
+    run.insert("tool".into(), json!({ "driver": driver }));
+    run.insert("results".into(), failed.map(result).collect());
+    if let Some(error) = plugin.error {
+        run.insert(
+            "invocations".into(),
+            json!([{
+                "executionSuccessful": false,
+                "toolExecutionNotifications": [{
+                    "level": "error",
+                    "message": { "text": error },
+                }],
+            }]),
+        );
+    }
+    Value::Object(run)
+}
+
+fn result(case: &TestCaseOutput) -> Value {
+    let mut result = Map::new();
+    if let Some(rule_id) = case.rule() {
+        result.insert("ruleId".into(), rule_id.into());
+    }
+    result.insert("level".into(), level(case).into());
+    result.insert(
+        "message".into(),
+        json!({ "text": case.error_message.clone().unwrap_or_else(|| "Failed".to_string()) }),
+    );
+
+    // SARIF lines and columns start at 1, anything else means the plugin didn't know
+    let mut region = Map::new();
+    if let Some(line) = case.line_no.filter(|line| *line > 0) {
+        region.insert("startLine".into(), line.into());
+        if let Some(column) = case.column_no.filter(|column| *column > 0) {
+            region.insert("startColumn".into(), column.into());
+        }
+    }
+
+    let mut location = Map::new();
+    location.insert(
+        "artifactLocation".into(),
+        json!({
+            "uri": uri(&case.file_name),
+            "uriBaseId": "%SRCROOT%",
+        }),
+    );
+    if !region.is_empty() {
+        location.insert("region".into(), Value::Object(region));
+    }
+    result.insert(
+        "locations".into(),
+        json!([{ "physicalLocation": location }]),
+    );
+
+    Value::Object(result)
+}
+
+/// A file path as a relative URI reference. Everything but unreserved characters
+/// and separators is percent-encoded, so paths with spaces or `#` stay valid.
+fn uri(path: &str) -> String {
+    let mut uri = String::with_capacity(path.len());
+    for byte in path.replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Maps `data.severity` to a SARIF level. Linters use numbers (eslint: 1 warning,
+/// 2 error) or names, results without a severity are errors.
+fn level(case: &TestCaseOutput) -> &'static str {
+    let severity = case.data.as_ref().and_then(|data| data.get("severity"));
+    match severity {
+        Some(Value::Number(number)) => match number.as_i64() {
+            Some(0) => "note",
+            Some(1) => "warning",
+            _ => "error",
+        },
+        Some(Value::String(name)) => match name.to_lowercase().as_str() {
+            "info" | "information" | "note" | "hint" => "note",
+            "warn" | "warning" => "warning",
+            _ => "error",
+        },
+        _ => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::exec::eval::LintPluginEvalOutput;
+    use std::collections::HashMap;
+
+    fn issue(file_name: &str, severity: Value) -> TestCaseOutput {
+        TestCaseOutput {
+            file_name: file_name.to_string(),
+            line_no: Some(3),
+            column_no: Some(7),
+            success: false,
+            error_message: Some("'x' is never used".to_string()),
+            rule_id: Some("no-unused-vars".to_string()),
+            data: Some(HashMap::from([("severity".to_string(), severity)])),
+        }
+    }
+
+    fn render_lint(results: Vec<TestCaseOutput>) -> Value {
+        let output = PluginEvalOutput::Lint(LintPluginEvalOutput {
+            total_errors: results.len() as u32,
+            lint_results: results,
+        });
+        let log = render(&[PluginResults {
+            plugin_id: "eslint",
+            version: Some("1.0.0"),
+            output: Some(&output),
+            error: None,
+        }])
+        .unwrap();
+        serde_json::from_str(&log).unwrap()
+    }
+
+    #[test]
+    fn renders_lint_results() {
+        let log = render_lint(vec![issue("src/my file.js", json!(1))]);
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "eslint");
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([{ "id": "no-unused-vars" }])
+        );
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "no-unused-vars");
+        assert_eq!(result["level"], "warning");
+        assert_eq!(result["message"]["text"], "'x' is never used");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/my%20file.js");
+        assert_eq!(
+            location["region"],
+            json!({ "startLine": 3, "startColumn": 7 })
+        );
+    }
+
+    #[test]
+    fn leaves_out_passing_results_and_unknown_positions() {
+        let mut passed = issue("a.js", json!(2));
+        passed.success = true;
+        let mut unknown = issue("b.js", json!("error"));
+        unknown.line_no = Some(0);
+        let log = render_lint(vec![passed, unknown]);
+
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["level"], "error");
+        assert!(results[0]["locations"][0]["physicalLocation"]
+            .get("region")
+            .is_none());
+    }
+
+    #[test]
+    fn percent_encodes_uris() {
+        assert_eq!(uri("src/a-b_c.d~e.js"), "src/a-b_c.d~e.js");
+        assert_eq!(uri("docs/my notes#1.md"), "docs/my%20notes%231.md");
+        assert_eq!(uri("src\\win\\a.js"), "src/win/a.js");
+        assert_eq!(uri("c:100%.js"), "c%3A100%25.js");
+        assert_eq!(uri("café.js"), "caf%C3%A9.js");
+    }
+}
@@ -0,0 +1,221 @@
+use flint_utils::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const GENERATED_FILE: &str = ".flint/generated.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GeneratedEntry {
+    pub plugin: String,
+    /// Hash of the contents flint wrote
+    pub hash: String,
+}
+
+/// Files written by `flint generate`, keyed by the name the plugin gave them. Lets
+/// flint tell files it owns apart from ones that were edited by hand since.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GeneratedFiles {
+    pub files: BTreeMap<String, GeneratedEntry>,
+}
+
+/// Where a generated file stands, compared to what was last generated and what
+/// its plugin would generate now
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    /// Up to date with what the plugin generates
+    Generated,
+    /// Edited since it was generated, or written by hand
+    Drifted,
+    /// Missing, or generated from an older config
+    Stale,
+    /// Generated by a plugin that isn't configured anymore, or doesn't generate it anymore
+    Orphaned,
+}
+
+impl FileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileStatus::Generated => "generated",
+            FileStatus::Drifted => "drifted",
+            FileStatus::Stale => "stale",
+            FileStatus::Orphaned => "orphaned",
+        }
+    }
+}
+
+impl GeneratedFiles {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(GENERATED_FILE)
+    }
+
+    /// The files recorded in `path`, none if nothing was generated yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, plugin_id: &str, file_name: &str, contents: &str) {
+        self.files.insert(
+            file_name.to_string(),
+            GeneratedEntry {
+                plugin: plugin_id.to_string(),
+                hash: hash(contents),
+            },
+        );
+    }
+
+    /// Whether a file was changed since flint generated it. Files that already have the
+    /// generated contents never count as changed, whether flint has a record of them or not.
+    pub fn drifted(&self, file_name: &str, current: Option<&str>, generated: &str) -> bool {
+        let Some(current) = current else {
+            return false;
+        };
+        if current == generated {
+            return false;
+        }
+        match self.files.get(file_name) {
+            Some(entry) => entry.hash != hash(current),
+            None => true,
+        }
+    }
+
+    /// The status of a file the plugin generates, given its current contents
+    pub fn status(&self, file_name: &str, current: Option<&str>, generated: &str) -> FileStatus {
+        if self.drifted(file_name, current, generated) {
+            FileStatus::Drifted
+        } else if current == Some(generated) {
+            FileStatus::Generated
+        } else {
+            FileStatus::Stale
+        }
+    }
+
+    /// Files flint generated before that still exist, but that `still_generated` (given the
+    /// file name and its plugin) says no plugin generates now. Returns their names and plugins.
+    pub fn orphaned(
+        &self,
+        project_dir: &Path,
+        still_generated: impl Fn(&str, &str) -> bool,
+    ) -> Vec<(String, String)> {
+        self.files
+            .iter()
+            .filter(|(file_name, entry)| {
+                !still_generated(file_name, &entry.plugin) && project_dir.join(file_name).exists()
+            })
+            .map(|(file_name, entry)| (file_name.clone(), entry.plugin.clone()))
+            .collect()
+    }
+}
+
+pub fn hash(contents: &str) -> String {
+    let digest = Sha256::digest(contents.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("sha256:{}", digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn recorded(file_name: &str, contents: &str) -> GeneratedFiles {
+        let mut generated = GeneratedFiles::default();
+        generated.record("x-lint", file_name, contents);
+        generated
+    }
+
+    #[test]
+    fn up_to_date_files_are_generated() {
+        let generated = recorded(".x.json", "new");
+        assert_eq!(
+            generated.status(".x.json", Some("new"), "new"),
+            FileStatus::Generated
+        );
+    }
+
+    #[test]
+    fn files_edited_since_they_were_generated_are_drifted() {
+        let generated = recorded(".x.json", "old");
+        assert_eq!(
+            generated.status(".x.json", Some("edited"), "new"),
+            FileStatus::Drifted
+        );
+        // Written by hand before flint ever generated it
+        assert_eq!(
+            GeneratedFiles::default().status(".x.json", Some("mine"), "new"),
+            FileStatus::Drifted
+        );
+    }
+
+    #[test]
+    fn files_edited_to_match_the_generated_contents_are_not_drifted() {
+        let generated = recorded(".x.json", "old");
+        assert!(!generated.drifted(".x.json", Some("new"), "new"));
+        assert_eq!(
+            generated.status(".x.json", Some("new"), "new"),
+            FileStatus::Generated
+        );
+    }
+
+    #[test]
+    fn missing_or_outdated_files_are_stale() {
+        let generated = recorded(".x.json", "old");
+        assert_eq!(
+            generated.status(".x.json", Some("old"), "new"),
+            FileStatus::Stale
+        );
+        assert_eq!(generated.status(".x.json", None, "new"), FileStatus::Stale);
+        assert_eq!(
+            GeneratedFiles::default().status(".x.json", None, "new"),
+            FileStatus::Stale
+        );
+    }
+
+    #[test]
+    fn existing_files_no_plugin_generates_are_orphaned() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".x.json"), "old").unwrap();
+        fs::write(dir.path().join(".y.json"), "old").unwrap();
+        let mut generated = recorded(".x.json", "old");
+        generated.record("y-lint", ".y.json", "old");
+        generated.record("x-lint", ".deleted.json", "old");
+
+        let orphaned = generated.orphaned(dir.path(), |file_name, _| file_name == ".y.json");
+        assert_eq!(
+            orphaned,
+            vec![(".x.json".to_string(), "x-lint".to_string())]
+        );
+        assert!(generated.orphaned(dir.path(), |_, _| true).is_empty());
+    }
+
+    #[test]
+    fn saves_and_loads_records() {
+        let dir = TempDir::new().unwrap();
+        let path = GeneratedFiles::path(dir.path());
+        assert!(GeneratedFiles::load(&path).unwrap().files.is_empty());
+
+        recorded(".x.json", "old").save(&path).unwrap();
+        let loaded = GeneratedFiles::load(&path).unwrap();
+        assert_eq!(loaded.files[".x.json"].hash, hash("old"));
+        assert_eq!(loaded.files[".x.json"].plugin, "x-lint");
+    }
+}
@@ -0,0 +1,514 @@
+use flint_utils::{app_err, get_flag, set_flag, warn, Result};
+use mlua::Lua;
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    process::{ExitStatus, Output},
+    sync::Arc,
+};
+
+use super::{
+    eval::PluginEvalOutput,
+    find::load_plugin,
+    generate::{generate_with, CiInputs},
+    run::{RunPlan, StepOutput},
+    validate::validate_plugin_structure,
+    Plugin, PluginKind,
+};
+use crate::util::toml::Config;
+
+/// Fixtures live in `fixtures/<name>/` inside the plugin directory
+pub const FIXTURES_DIR: &str = "fixtures";
+
+// Recorded tool output, fed to Eval()
+const STDOUT_FILE: &str = "stdout.txt";
+const STDERR_FILE: &str = "stderr.txt";
+const STATUS_FILE: &str = "status";
+// Holds a directory of recorded output per step, for plugins that run several commands
+const STEPS_DIR: &str = "steps";
+// What the other plugins in the project need, fed to a CI plugin's Generate()
+const DEPENDENCIES_FILE: &str = "dependencies.json";
+const ENV_FILE: &str = "env.json";
+// Results of a lint or test plugin, fed to a report plugin's Run()
+const REPORT_INPUT_FILE: &str = "report_input.json";
+
+// Snapshots
+const EXPECTED_RUN_FILE: &str = "expected_run.json";
+const EXPECTED_EVAL_FILE: &str = "expected_eval.json";
+const EXPECTED_GENERATE_DIR: &str = "expected_generate";
+const EXPECTED_REPORT_DIR: &str = "expected_report";
+
+/// What a report plugin is called with in a fixture
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReportInput {
+    pub plugin_id: String,
+    pub output: PluginEvalOutput,
+    #[serde(default)]
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    Run,
+    Eval,
+    Generate,
+    Report,
+}
+
+impl Check {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::Run => "Run",
+            Check::Eval => "Eval",
+            Check::Generate => "Generate",
+            Check::Report => "Report",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    Passed,
+    Updated,
+    /// Holds a diff of the expected and actual results, or the plugin's error
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub fixture: String,
+    pub check: Check,
+    pub outcome: Outcome,
+}
+
+/// Loads the plugin in `dir`. Its kind is taken from `kind`, the directory it is
+/// installed in (e.g. plugins/lint/eslint), or the section its fixtures configure it in.
+pub fn load(dir: &Path, kind: Option<PluginKind>) -> Result<Plugin> {
+    let dir = dir.canonicalize()?;
+    let lua = Lua::new();
+    flint_ffi::add_ffi_modules(&lua)?;
+
+    let mut plugin = load_plugin(&lua, dir.clone(), kind.clone().unwrap_or(PluginKind::Lint))?;
+    if kind.is_none() {
+        plugin.kind = infer_kind(&plugin)?;
+    }
+
+    validate_plugin_structure(&plugin)?;
+    Ok(plugin)
+}
+
+fn infer_kind(plugin: &Plugin) -> Result<PluginKind> {
+    let parent = plugin
+        .path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .map(|name| name.to_string_lossy().to_string());
+
+    match parent.as_deref() {
+        Some("lint") => return Ok(PluginKind::Lint),
+        Some("test") => return Ok(PluginKind::Test),
+        Some("ci") => return Ok(PluginKind::Ci),
+        Some("report") => return Ok(PluginKind::Report),
+        _ => {}
+    }
+
+    for fixture in fixtures(plugin)? {
+        let Ok(config) = Config::load(&fixture.join("flint.toml")) else {
+            continue;
+        };
+
+        let id = &plugin.details.id;
+        if config.rules.contains_key(id) {
+            return Ok(PluginKind::Lint);
+        } else if config.tests.contains_key(id) {
+            return Ok(PluginKind::Test);
+        } else if config.ci.contains_key(id) {
+            return Ok(PluginKind::Ci);
+        } else if config.report.contains_key(id) {
+            return Ok(PluginKind::Report);
+        }
+    }
+
+    app_err!(
+        "Could not tell what kind of plugin {} is, pass it with --kind",
+        plugin.details.id
+    )
+}
+
+/// The fixture directories of a plugin, sorted by name
+pub fn fixtures(plugin: &Plugin) -> Result<Vec<PathBuf>> {
+    let fixtures_dir = plugin.path.join(FIXTURES_DIR);
+    if !fixtures_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut fixtures = Vec::new();
+    for entry in fs::read_dir(fixtures_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            fixtures.push(entry.path());
+        }
+    }
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// Runs every check a fixture has a snapshot for. With `update`, snapshots are
+/// (re)written from the plugin's current output instead of compared against.
+pub fn run_fixture(plugin: &Plugin, fixture: &Path, update: bool) -> Result<Vec<CheckResult>> {
+    let name = fixture
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let checks = match plugin.kind {
+        PluginKind::Lint | PluginKind::Test => vec![Check::Run, Check::Eval, Check::Generate],
+        PluginKind::Ci => vec![Check::Generate],
+        PluginKind::Report => vec![Check::Report],
+    };
+
+    // Plugins see the fixture as the project they are run in
+    let config_path = fixture.join("flint.toml");
+    let config = Arc::new(load_config(plugin, &config_path)?);
+    let previous_dir = get_flag!(current_dir);
+    let previous_config_path = get_flag!(config_path);
+    set_flag!(current_dir, fixture.to_path_buf());
+    set_flag!(config_path, config_path);
+
+    let results = checks
+        .into_iter()
+        .filter(|check| {
+            if update {
+                has_inputs(fixture, *check)
+            } else {
+                fixture.join(snapshot_path(*check)).exists()
+            }
+        })
+        .map(|check| {
+            let outcome = match run_check(plugin, fixture, &config, check, update) {
+                Ok(outcome) => outcome,
+                Err(err) => Outcome::Failed(err.to_string()),
+            };
+            CheckResult {
+                fixture: name.clone(),
+                check,
+                outcome,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    set_flag!(current_dir, previous_dir);
+    set_flag!(config_path, previous_config_path);
+
+    if results.is_empty() {
+        warn!(
+            "Fixture {} has nothing to check, run `flint plugin test --update` to record its snapshots",
+            name
+        );
+    }
+
+    Ok(results)
+}
+
+// Fixtures without a flint.toml configure the plugin with an empty table, so only schema defaults apply
+fn load_config(plugin: &Plugin, path: &PathBuf) -> Result<Config> {
+    if path.exists() {
+        return Config::load(path);
+    }
+
+    let config = format!(
+        "[flint]\nversion = 1\n\n[{}.\"{}\"]\n",
+        plugin.kind.config_section(),
+        plugin.details.id
+    );
+    Ok(toml::from_str(&config)?)
+}
+
+fn snapshot_path(check: Check) -> &'static str {
+    match check {
+        Check::Run => EXPECTED_RUN_FILE,
+        Check::Eval => EXPECTED_EVAL_FILE,
+        Check::Generate => EXPECTED_GENERATE_DIR,
+        Check::Report => EXPECTED_REPORT_DIR,
+    }
+}
+
+fn has_inputs(fixture: &Path, check: Check) -> bool {
+    match check {
+        Check::Run | Check::Generate => true,
+        Check::Eval => [STDOUT_FILE, STDERR_FILE, STATUS_FILE, STEPS_DIR]
+            .iter()
+            .any(|file| fixture.join(file).exists()),
+        Check::Report => fixture.join(REPORT_INPUT_FILE).exists(),
+    }
+}
+
+fn run_check(
+    plugin: &Plugin,
+    fixture: &Path,
+    config: &Arc<Config>,
+    check: Check,
+    update: bool,
+) -> Result<Outcome> {
+    let snapshot = fixture.join(snapshot_path(check));
+
+    match check {
+        Check::Run => {
+            let plan = plugin.run(config, None)?;
+            compare_json(&snapshot, plan_snapshot(&plan)?, update)
+        }
+        Check::Eval => {
+            let outputs = recorded_outputs(plugin, fixture, config)?;
+            let eval_output = plugin.eval(&outputs, config)?;
+            compare_json(&snapshot, serde_json::to_value(eval_output)?, update)
+        }
+        Check::Generate => {
+            let ci_inputs = match plugin.kind {
+                PluginKind::Ci => Some(ci_inputs(fixture)?),
+                _ => None,
+            };
+            let files = generate_with(plugin, config, ci_inputs.as_ref())?;
+            compare_files(&snapshot, files.into_iter().collect(), update)
+        }
+        Check::Report => {
+            let input: ReportInput =
+                serde_json::from_str(&fs::read_to_string(fixture.join(REPORT_INPUT_FILE))?)?;
+            let files = plugin.report(config, &input.output, &input.plugin_id, input.cached)?;
+            compare_files(&snapshot, files.into_iter().collect(), update)
+        }
+    }
+}
+
+// The fixture stands in for the project's other plugins, so results don't depend on what is installed
+fn ci_inputs(fixture: &Path) -> Result<CiInputs> {
+    let dependencies = match fs::read_to_string(fixture.join(DEPENDENCIES_FILE)) {
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(_) => HashMap::new(),
+    };
+    let env = match fs::read_to_string(fixture.join(ENV_FILE)) {
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(_) => HashMap::new(),
+    };
+    Ok(CiInputs { dependencies, env })
+}
+
+/// Run() snapshots look like what Run() returned: a list for plain commands,
+/// a command table, or a table of steps
+fn plan_snapshot(plan: &RunPlan) -> Result<serde_json::Value> {
+    if plan.is_single() {
+        let command = &plan.steps[0].command;
+        return Ok(if command.is_argv() {
+            serde_json::to_value(command.argv())?
+        } else {
+            serde_json::to_value(command)?
+        });
+    }
+
+    let mut steps = Vec::new();
+    for step in &plan.steps {
+        let mut value = serde_json::to_value(&step.command)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.insert("name".to_string(), step.name.clone().into());
+            fields.insert(
+                "continue_on_failure".to_string(),
+                step.continue_on_failure.into(),
+            );
+        }
+        steps.push(value);
+    }
+    Ok(serde_json::json!({ "steps": steps }))
+}
+
+/// The recorded outputs of a fixture. Multi-step runs are recorded in `steps/<name>/`,
+/// and are passed to Eval() in the order Run() lists them.
+fn recorded_outputs(
+    plugin: &Plugin,
+    fixture: &Path,
+    config: &Arc<Config>,
+) -> Result<Vec<StepOutput>> {
+    let steps_dir = fixture.join(STEPS_DIR);
+    if !steps_dir.exists() {
+        return Ok(vec![StepOutput {
+            name: RunPlan::SINGLE_STEP.to_string(),
+            output: recorded_output(fixture)?,
+            truncated: false,
+        }]);
+    }
+
+    let mut recorded = Vec::new();
+    for entry in fs::read_dir(&steps_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            recorded.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    recorded.sort();
+
+    // Steps Run() doesn't know about go last
+    let order = plugin
+        .run(config, None)
+        .map(|plan| plan.steps.into_iter().map(|step| step.name).collect())
+        .unwrap_or_else(|_| Vec::new());
+    recorded.sort_by_key(|name| {
+        order
+            .iter()
+            .position(|step| step == name)
+            .unwrap_or(usize::MAX)
+    });
+
+    recorded
+        .into_iter()
+        .map(|name| {
+            let output = recorded_output(&steps_dir.join(&name))?;
+            Ok(StepOutput {
+                name,
+                output,
+                truncated: false,
+            })
+        })
+        .collect()
+}
+
+/// Builds the output of a command from the stdout, stderr and exit status saved in a fixture
+fn recorded_output(fixture: &Path) -> Result<Output> {
+    let read = |file: &str| fs::read(fixture.join(file)).unwrap_or_default();
+
+    let status = match fs::read_to_string(fixture.join(STATUS_FILE)) {
+        Ok(status) => match status.trim().parse::<i32>() {
+            Ok(code) => code,
+            Err(_) => {
+                return app_err!(
+                    "{} should contain an exit code, found \"{}\"",
+                    fixture.join(STATUS_FILE).display(),
+                    status.trim()
+                )
+            }
+        },
+        Err(_) => 0,
+    };
+
+    Ok(Output {
+        status: exit_status(status),
+        stdout: read(STDOUT_FILE),
+        stderr: read(STDERR_FILE),
+    })
+}
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    // The raw value is a wait status, which keeps the exit code in the second byte
+    ExitStatus::from_raw((code & 0xff) << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(code as u32)
+}
+
+fn compare_json(snapshot: &Path, actual: serde_json::Value, update: bool) -> Result<Outcome> {
+    let actual_str = serde_json::to_string_pretty(&actual)? + "\n";
+
+    if update {
+        fs::write(snapshot, actual_str)?;
+        return Ok(Outcome::Updated);
+    }
+
+    let expected: serde_json::Value = serde_json::from_str(&fs::read_to_string(snapshot)?)?;
+    if expected == actual {
+        return Ok(Outcome::Passed);
+    }
+
+    let expected_str = serde_json::to_string_pretty(&expected)? + "\n";
+    Ok(Outcome::Failed(diff(
+        &snapshot_name(snapshot),
+        &expected_str,
+        &actual_str,
+    )))
+}
+
+fn compare_files(
+    snapshot: &Path,
+    actual: BTreeMap<String, String>,
+    update: bool,
+) -> Result<Outcome> {
+    if update {
+        if snapshot.exists() {
+            fs::remove_dir_all(snapshot)?;
+        }
+        fs::create_dir_all(snapshot)?;
+        for (file, contents) in &actual {
+            let path = snapshot.join(file);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, contents)?;
+        }
+        return Ok(Outcome::Updated);
+    }
+
+    let mut expected = BTreeMap::new();
+    read_files(snapshot, snapshot, &mut expected)?;
+
+    let mut problems = Vec::new();
+    for (file, contents) in &expected {
+        match actual.get(file) {
+            None => problems.push(format!("{} was not generated", file)),
+            Some(actual_contents) if actual_contents != contents => {
+                problems.push(diff(file, contents, actual_contents))
+            }
+            Some(_) => {}
+        }
+    }
+    for file in actual.keys().filter(|file| !expected.contains_key(*file)) {
+        problems.push(format!("{} was generated but is not in the snapshot", file));
+    }
+
+    if problems.is_empty() {
+        Ok(Outcome::Passed)
+    } else {
+        Ok(Outcome::Failed(problems.join("\n")))
+    }
+}
+
+// Keys use forward slashes, the same way plugins return file names
+fn read_files(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            read_files(root, &path, files)?;
+        } else {
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(relative, fs::read_to_string(&path)?);
+        }
+    }
+    Ok(())
+}
+
+fn snapshot_name(snapshot: &Path) -> String {
+    snapshot
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn diff(name: &str, expected: &str, actual: &str) -> String {
+    TextDiff::from_lines(expected, actual)
+        .unified_diff()
+        .header(
+            &format!("{} (expected)", name),
+            &format!("{} (actual)", name),
+        )
+        .to_string()
+}
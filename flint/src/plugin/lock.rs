@@ -0,0 +1,128 @@
+use flint_utils::{get_flag, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::PluginKind;
+
+pub const LOCKFILE_NAME: &str = "flint.lock";
+const LOCKFILE_VERSION: u8 = 1;
+
+/// A plugin pinned to the exact commit (and contents) it was installed from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedPlugin {
+    pub id: String,
+    pub kind: PluginKind,
+    pub source: String,
+    /// The branch, tag or commit that was asked for in flint.toml
+    #[serde(rename = "ref", default)]
+    pub reference: String,
+    /// The commit it resolved to
+    pub rev: String,
+    pub checksum: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Lockfile {
+    pub version: u8,
+    #[serde(default, rename = "plugin")]
+    pub plugins: Vec<LockedPlugin>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            plugins: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// The lockfile lives next to flint.toml
+    pub fn path() -> PathBuf {
+        get_flag!(current_dir).join(LOCKFILE_NAME)
+    }
+
+    /// Loads the lockfile, returning an empty one if it doesn't exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.plugins
+            .sort_by(|a, b| (&a.kind, &a.id).cmp(&(&b.kind, &b.id)));
+
+        let contents = format!(
+            "# This file is generated by `flint install` and `flint update`. Do not edit it by hand.\n{}",
+            toml::to_string(self)?
+        );
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn get(&self, kind: &PluginKind, id: &str) -> Option<&LockedPlugin> {
+        self.plugins
+            .iter()
+            .find(|plugin| &plugin.kind == kind && plugin.id == id)
+    }
+
+    /// Adds a plugin to the lockfile, replacing any existing entry for it
+    pub fn insert(&mut self, locked: LockedPlugin) {
+        self.plugins
+            .retain(|plugin| !(plugin.kind == locked.kind && plugin.id == locked.id));
+        self.plugins.push(locked);
+    }
+}
+
+/// Hashes the contents of a plugin directory. Files are visited in a stable
+/// order and their relative paths are hashed too, so renames change the checksum.
+pub fn checksum_dir(dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for relative in files {
+        let contents = fs::read(dir.join(&relative))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+
+    let digest = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    Ok(format!("sha256:{}", digest))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            // Use forward slashes so checksums match across platforms
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
@@ -1,20 +1,38 @@
 use crate::util::toml::Config;
-use flint_utils::Result;
+use flint_utils::{app_err, get_flag, Error, Result};
 
 pub mod find;
 use deps::Dependency;
 use eval::PluginEvalOutput;
 pub use find::*;
+pub mod baseline;
+pub mod cache;
+pub mod common;
 pub mod deps;
 pub mod download;
 pub mod exec;
+pub mod format;
+pub mod generated;
+pub mod harness;
+pub mod lock;
+pub mod process;
+pub mod result_cache;
+pub mod scaffold;
+pub mod schedule;
+pub mod schema;
+pub mod suppress;
 pub mod validate;
+pub mod verdict;
+pub mod version;
+
+#[cfg(test)]
+pub(crate) mod testing;
 
 use exec::*;
 
-use mlua::{Lua, LuaSerdeExt, Table};
+use mlua::{Function, Lua, LuaSerdeExt, Table, Value};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, process::Output, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 #[derive(Serialize, Deserialize, Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
 pub struct PluginDetails {
@@ -22,6 +40,10 @@ pub struct PluginDetails {
     pub extensions: Vec<String>,
     pub version: String,
     pub author: String,
+    /// Semver requirement on the plugin API, e.g. "^1.1"
+    pub api_version: Option<String>,
+    /// Semver requirement on Flint itself, e.g. ">=0.2"
+    pub flint_version: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
@@ -31,7 +53,10 @@ pub struct Plugin {
     pub kind: PluginKind,
 }
 
-#[derive(Serialize, Deserialize, Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
+#[derive(
+    Serialize, Deserialize, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Clone, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum PluginKind {
     Lint,
     Test,
@@ -48,68 +73,179 @@ impl PluginKind {
             PluginKind::Report => "report".to_string(),
         }
     }
+
+    /// The flint.toml section plugins of this kind are configured in
+    pub fn config_section(&self) -> &'static str {
+        match self {
+            PluginKind::Lint => "rules",
+            PluginKind::Test => "tests",
+            PluginKind::Ci => "ci",
+            PluginKind::Report => "report",
+        }
+    }
 }
 
 impl Plugin {
-    pub fn get_config_lua(&self, lua: &Lua, toml: &Arc<Config>) -> Table {
-        let temp_default = toml::Value::Table(toml::map::Map::new());
-        let common_config = toml.rules.get("common").unwrap_or(&temp_default);
-        let common_config = lua
-            .to_value(&common_config)
-            .expect("unable to convert common config to lua value");
-
-        let plugin_config = match self.kind {
+    /// The plugin's table in flint.toml, as written (without schema defaults)
+    pub fn raw_config<'a>(&self, toml: &'a Config) -> Option<&'a toml::Value> {
+        match self.kind {
             PluginKind::Lint => toml.rules.get(&self.details.id),
             PluginKind::Test => toml.tests.get(&self.details.id),
             PluginKind::Ci => toml.ci.get(&self.details.id),
             PluginKind::Report => toml.report.get(&self.details.id),
         }
-        .expect(format!("unable to find config for plugin - {}", self.details.id).as_str());
+    }
+
+    /// A list of strings set by flint (not the plugin) in the plugin's table, e.g. `after`
+    pub fn string_list(&self, toml: &Config, key: &str) -> Result<Vec<String>> {
+        let Some(value) = self.raw_config(toml).and_then(|config| config.get(key)) else {
+            return Ok(vec![]);
+        };
 
-        let plugin_config = lua
-            .to_value(plugin_config)
-            .expect("unable to convert plugin config to lua value");
-        let plugin_config = plugin_config
-            .as_table()
-            .expect("unable to convert plugin config lua value to table");
+        let list = value.as_array().and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        });
 
-        plugin_config
-            .set("common", common_config)
-            .expect("unable to set common table to config table");
+        match list {
+            Some(list) => Ok(list),
+            None => app_err!(
+                "{} of plugin {} should be a list of strings, found {}",
+                key,
+                self.details.id,
+                value
+            ),
+        }
+    }
+
+    /// The config the plugin's functions get: its table in flint.toml with schema
+    /// defaults filled in, plus `common` and (for lint plugins) `config`
+    pub fn resolved_config(&self, toml: &Config) -> Result<toml::Value> {
+        let Some(plugin_config) = self.raw_config(toml) else {
+            return Err(Error::PluginConfigMissing {
+                plugin_id: self.details.id.clone(),
+                section: self.kind.config_section().to_string(),
+                file: get_flag!(config_path),
+            });
+        };
+
+        let mut plugin_config = plugin_config.clone();
+        if let Some(schema) = self.get_schema()? {
+            schema::apply_defaults(&mut plugin_config, &schema);
+        }
+
+        let type_str = plugin_config.type_str();
+        let toml::Value::Table(ref mut table) = plugin_config else {
+            return app_err!(
+                "Config for plugin {} should be a table, found {}",
+                self.details.id,
+                type_str
+            );
+        };
+
+        let common_config = toml
+            .rules
+            .get("common")
+            .cloned()
+            .unwrap_or(toml::Value::Table(toml::map::Map::new()));
+        table.insert("common".to_string(), common_config);
 
         if self.kind == PluginKind::Lint {
-            if let Some(temp) = toml.config.get(&self.details.id) {
-                let extra_config = lua.to_value(temp).expect(
-                    format!(
-                        "unable to convert config.{}.extra to lua value",
-                        self.details.id
-                    )
-                    .as_str(),
-                );
-                plugin_config.set("config", extra_config).expect(&format!(
-                    "unable to set extra config for plugin {}",
-                    self.details.id
-                ))
+            if let Some(extra_config) = toml.config.get(&self.details.id) {
+                table.insert("config".to_string(), extra_config.clone());
             }
         }
 
-        plugin_config.clone()
+        Ok(plugin_config)
+    }
+
+    pub fn get_config_lua(&self, lua: &Lua, toml: &Arc<Config>) -> Result<Table> {
+        match lua.to_value(&self.resolved_config(toml)?)? {
+            Value::Table(table) => Ok(table),
+            value => app_err!(
+                "Config for plugin {} should be a table, found {}",
+                self.details.id,
+                value.type_name()
+            ),
+        }
+    }
+
+    /// Loads and executes one of the plugin's lua files (e.g. run.lua)
+    pub fn load_file(&self, lua: &Lua, file: &str) -> Result<()> {
+        let path = self.path.join(file);
+        let load_error = |traceback: String| Error::PluginLoad {
+            plugin_id: self.details.id.clone(),
+            file: path.clone(),
+            traceback,
+        };
+
+        let contents = std::fs::read_to_string(&path).map_err(|err| load_error(err.to_string()))?;
+        lua.load(contents)
+            .set_name(format!("@{}", path.display()))
+            .exec()
+            .map_err(|err| load_error(lua_traceback(&err)))
+    }
+
+    /// Loads a lua file and returns a global function it is required to define
+    pub fn get_function(&self, lua: &Lua, file: &str, name: &str) -> Result<Function> {
+        self.load_file(lua, file)?;
+        match lua.globals().get::<Value>(name)? {
+            Value::Function(func) => Ok(func),
+            Value::Nil => Err(self.contract_error(file, format!("{}() is not defined", name))),
+            other => Err(self.contract_error(
+                file,
+                format!("{} should be a function, found {}", name, other.type_name()),
+            )),
+        }
+    }
+
+    /// Wraps an error raised by lua while calling one of the plugin's functions
+    pub fn runtime_error(&self, file: &str, err: mlua::Error) -> Error {
+        Error::PluginRuntime {
+            plugin_id: self.details.id.clone(),
+            file: self.path.join(file),
+            traceback: lua_traceback(&err),
+        }
+    }
+
+    /// An error for when a plugin function returns something flint doesn't understand
+    pub fn contract_error(&self, file: &str, message: impl Into<String>) -> Error {
+        Error::PluginContract {
+            plugin_id: self.details.id.clone(),
+            file: self.path.join(file),
+            message: message.into(),
+        }
     }
 
     pub fn get_dependencies(&self) -> Result<HashMap<String, Vec<Dependency>>> {
         deps::get_dependencies(&self)
     }
 
+    pub fn get_schema(&self) -> Result<Option<schema::ConfigSchema>> {
+        schema::get_schema(self)
+    }
+
+    pub fn get_common_rules(&self) -> Result<Option<common::CommonRules>> {
+        common::get_common_rules(self)
+    }
+
     pub fn generate(&self, toml: &Arc<Config>) -> Result<HashMap<String, String>> {
         generate::generate(&self, toml)
     }
 
-    pub fn run<'a>(&self, toml: &Arc<Config>) -> Result<Vec<String>> {
-        run::run(&self, toml)
+    /// `files` limits the run to those files, when only changed files are checked
+    pub fn run<'a>(&self, toml: &Arc<Config>, files: Option<&[String]>) -> Result<run::RunPlan> {
+        run::run(&self, toml, files)
     }
 
-    pub fn eval(&self, output: Output, config: &Arc<Config>) -> Result<PluginEvalOutput> {
-        eval::eval(&self, output, config)
+    pub fn eval(
+        &self,
+        steps: &[run::StepOutput],
+        config: &Arc<Config>,
+    ) -> Result<PluginEvalOutput> {
+        eval::eval(&self, steps, config)
     }
 
     pub fn report(
@@ -117,23 +253,32 @@ impl Plugin {
         toml: &Arc<Config>,
         output: &PluginEvalOutput,
         plugin_id: &str,
+        cached: bool,
     ) -> Result<HashMap<String, String>> {
-        report::report(&self, toml, output, plugin_id)
+        report::report(&self, toml, output, plugin_id, cached)
+    }
+}
+
+// Callback errors carry the traceback separately from the message
+fn lua_traceback(err: &mlua::Error) -> String {
+    match err {
+        mlua::Error::CallbackError { traceback, cause } => format!("{}\n{}", cause, traceback),
+        err => err.to_string(),
     }
 }
 
-pub fn list_from_config(config: &Arc<Config>) -> Vec<Plugin> {
+pub fn list_from_config(config: &Arc<Config>) -> Result<Vec<Plugin>> {
     let mut plugin_ids = Vec::new();
     plugin_ids.extend(config.rules.keys());
     plugin_ids.extend(config.tests.keys());
     plugin_ids.extend(config.report.keys());
     plugin_ids.extend(config.ci.keys());
 
-    let plugins = find::list().unwrap();
+    let plugins = find::list()?;
 
-    plugins
+    Ok(plugins
         .iter()
         .filter(|plugin| plugin_ids.contains(&&plugin.details.id))
         .cloned()
-        .collect()
+        .collect())
 }
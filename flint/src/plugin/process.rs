@@ -0,0 +1,379 @@
+use flint_utils::{app_err, info, warn, Result};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Child, Command, Output, Stdio},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::{
+    run::{RunCommand, RunPlan, StepOutput},
+    Plugin,
+};
+use crate::util::toml::Config;
+
+const DEFAULT_KILL_GRACE: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a plugin's command may run, e.g. `timeout = 300` in `[tests.jest]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunLimits {
+    /// Kill the command after this long (default: no limit)
+    pub timeout: Option<Duration>,
+    /// How long to wait after asking the command to stop before killing it
+    pub kill_grace: Duration,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            kill_grace: DEFAULT_KILL_GRACE,
+        }
+    }
+}
+
+impl RunLimits {
+    pub fn from_config(plugin: &Plugin, toml: &Config) -> Result<Self> {
+        let mut limits = Self::default();
+        let Some(config) = plugin.raw_config(toml) else {
+            return Ok(limits);
+        };
+
+        if let Some(timeout) = config.get("timeout") {
+            limits.timeout = Some(seconds(plugin, "timeout", timeout)?);
+        }
+        if let Some(kill_grace) = config.get("kill_grace") {
+            limits.kill_grace = seconds(plugin, "kill_grace", kill_grace)?;
+        }
+        Ok(limits)
+    }
+}
+
+fn seconds(plugin: &Plugin, key: &str, value: &toml::Value) -> Result<Duration> {
+    let secs = match value {
+        toml::Value::Integer(secs) => *secs as f64,
+        toml::Value::Float(secs) => *secs,
+        _ => f64::NAN,
+    };
+
+    // Also rejects NaN, negative values and ones too large for a Duration
+    match Duration::try_from_secs_f64(secs) {
+        Ok(duration) => Ok(duration),
+        Err(_) => app_err!(
+            "{} of plugin {} should be a number of seconds, found {}",
+            key,
+            plugin.details.id,
+            value
+        ),
+    }
+}
+
+pub enum RunResult {
+    /// `truncated` is set when the output was still open after the command exited,
+    /// so only part of it was read
+    Finished { output: Output, truncated: bool },
+    /// The command was killed after running for longer than the timeout
+    TimedOut(Duration),
+}
+
+pub enum PlanResult {
+    /// The outputs of every step
+    Finished(Vec<StepOutput>),
+    /// A step that wasn't allowed to fail exited with an error, so the steps after it were skipped
+    StepFailed(StepOutput),
+    /// A step was killed after the run went on for longer than the timeout
+    TimedOut(Duration),
+}
+
+/// Runs the steps of a plugin's run in order. The timeout in flint.toml covers the
+/// whole run, otherwise each step is limited by its own timeout.
+pub fn run_plan(
+    plugin_id: &str,
+    plan: &RunPlan,
+    project_dir: &Path,
+    limits: &RunLimits,
+) -> Result<PlanResult> {
+    let started = Instant::now();
+    let mut outputs = Vec::new();
+
+    for (index, step) in plan.steps.iter().enumerate() {
+        let mut step_limits = *limits;
+        if let Some(timeout) = limits.timeout {
+            step_limits.timeout = Some(timeout.saturating_sub(started.elapsed()));
+        }
+
+        let label = if plan.is_single() {
+            plugin_id.to_string()
+        } else {
+            info!(
+                "Running step {} of {}: {}",
+                step.name, plugin_id, step.command
+            );
+            format!("{}:{}", plugin_id, step.name)
+        };
+
+        let (output, truncated) =
+            match run_command(&label, &step.command, project_dir, &step_limits)? {
+                RunResult::Finished { output, truncated } => (output, truncated),
+                RunResult::TimedOut(timeout) => {
+                    return Ok(PlanResult::TimedOut(limits.timeout.unwrap_or(timeout)))
+                }
+            };
+
+        let step_output = StepOutput {
+            name: step.name.clone(),
+            output,
+            truncated,
+        };
+
+        // The last step failing is how most tools report problems, so leave that to Eval()
+        let is_last = index + 1 == plan.steps.len();
+        if !step_output.output.status.success() && !step.continue_on_failure && !is_last {
+            warn!(
+                "Step {} of {} failed, skipping the remaining steps",
+                step.name, plugin_id
+            );
+            return Ok(PlanResult::StepFailed(step_output));
+        }
+        outputs.push(step_output);
+    }
+
+    Ok(PlanResult::Finished(outputs))
+}
+
+/// Runs a single command, forwarding its stdout and stderr to the logs line by
+/// line as they arrive, and killing it if it runs for longer than its timeout.
+/// The timeout in flint.toml takes precedence over the one the plugin asks for.
+pub fn run_command(
+    plugin_id: &str,
+    command: &RunCommand,
+    project_dir: &Path,
+    limits: &RunLimits,
+) -> Result<RunResult> {
+    let timeout = limits
+        .timeout
+        // parse_command already rejected timeouts that don't fit in a Duration
+        .or(command
+            .timeout
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok()));
+    let cwd = match &command.cwd {
+        Some(cwd) => project_dir.join(cwd),
+        None => project_dir.to_path_buf(),
+    };
+
+    let mut process = Command::new(&command.cmd);
+    process
+        .args(&command.args)
+        .envs(&command.env)
+        .current_dir(cwd)
+        .stdin(match command.stdin {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // Put the command in its own process group, so tools that spawn workers
+    // (e.g. jest) can be stopped along with all of their children
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        process.process_group(0);
+    }
+
+    let mut child = process.spawn()?;
+    let stdout = forward_lines(plugin_id, child.stdout.take());
+    let stderr = forward_lines(plugin_id, child.stderr.take());
+
+    // Written from a thread so a command that doesn't read its input can't block us.
+    // Dropping stdin afterwards closes it, so the command sees the end of its input.
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), command.stdin.clone()) {
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+            stop(plugin_id, &mut child, limits.kill_grace)?;
+            break None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    // Anything the command left running in the background may still hold its
+    // output open, so don't wait for the readers forever
+    let (stdout, stdout_complete) = stdout.collect(limits.kill_grace);
+    let (stderr, stderr_complete) = stderr.collect(limits.kill_grace);
+    let truncated = !(stdout_complete && stderr_complete);
+    if truncated && status.is_some() {
+        warn!(
+            "{} exited, but its output was still open after {:?}, only part of it was read",
+            plugin_id, limits.kill_grace
+        );
+    }
+
+    Ok(match status {
+        Some(status) => RunResult::Finished {
+            output: Output {
+                status,
+                stdout,
+                stderr,
+            },
+            truncated,
+        },
+        None => RunResult::TimedOut(timeout.unwrap_or_default()),
+    })
+}
+
+/// Asks the command to stop, and kills it if it is still running after `kill_grace`
+fn stop(plugin_id: &str, child: &mut Child, kill_grace: Duration) -> Result<()> {
+    warn!("{} timed out, stopping it", plugin_id);
+
+    #[cfg(unix)]
+    {
+        let group = -(child.id() as i32);
+        // SAFETY: kill only sends a signal, the process group was created for this command
+        unsafe { libc::kill(group, libc::SIGTERM) };
+
+        let asked = Instant::now();
+        while asked.elapsed() < kill_grace {
+            if child.try_wait()?.is_some() {
+                // The command is gone, but its children may have ignored the signal
+                unsafe { libc::kill(group, libc::SIGKILL) };
+                return Ok(());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        warn!(
+            "{} did not stop within {:?}, killing it",
+            plugin_id, kill_grace
+        );
+        unsafe { libc::kill(group, libc::SIGKILL) };
+    }
+
+    // There is no way to ask nicely on other platforms
+    #[cfg(not(unix))]
+    let _ = kill_grace;
+
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+struct LineReader {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LineReader {
+    /// What was read so far, and whether the stream was read to the end. Gives up after
+    /// `wait`, as a process the command left running can hold the stream open. The
+    /// reader thread is left detached then: joining it would wait for that process,
+    /// and it ends by itself once the stream is closed.
+    fn collect(self, wait: Duration) -> (Vec<u8>, bool) {
+        let complete = match self.handle {
+            Some(handle) => {
+                let started = Instant::now();
+                while !handle.is_finished() && started.elapsed() < wait {
+                    thread::sleep(POLL_INTERVAL);
+                }
+                let complete = handle.is_finished();
+                if complete {
+                    let _ = handle.join();
+                }
+                complete
+            }
+            None => true,
+        };
+
+        let buffer = self.buffer.lock().unwrap();
+        (buffer.clone(), complete)
+    }
+}
+
+fn forward_lines(plugin_id: &str, stream: Option<impl Read + Send + 'static>) -> LineReader {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let Some(stream) = stream else {
+        return LineReader {
+            buffer,
+            handle: None,
+        };
+    };
+
+    let plugin_id = plugin_id.to_string();
+    let thread_buffer = Arc::clone(&buffer);
+    let handle = thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        // Tools don't always print valid UTF-8, so keep the raw bytes for Eval()
+        while let Ok(read) = reader.read_until(b'\n', &mut line) {
+            if read == 0 {
+                break;
+            }
+            info!(
+                "[{}] {}",
+                plugin_id,
+                String::from_utf8_lossy(&line).trim_end()
+            );
+            thread_buffer.lock().unwrap().extend_from_slice(&line);
+            line.clear();
+        }
+    });
+
+    LineReader {
+        buffer,
+        handle: Some(handle),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> RunCommand {
+        RunCommand {
+            cmd: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn run(script: &str) -> (Output, bool) {
+        let limits = RunLimits {
+            timeout: None,
+            kill_grace: Duration::from_millis(200),
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        match run_command("x-test", &sh(script), tmp.path(), &limits).unwrap() {
+            RunResult::Finished { output, truncated } => (output, truncated),
+            RunResult::TimedOut(_) => panic!("{:?} timed out", script),
+        }
+    }
+
+    #[test]
+    fn reads_output_to_the_end() {
+        let (output, truncated) = run("echo out; echo err >&2");
+        assert!(!truncated);
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn flags_output_held_open_by_a_background_process() {
+        let started = Instant::now();
+        let (output, truncated) = run("sleep 5 & echo partial");
+        assert!(truncated);
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"partial\n");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,327 @@
+use flint_utils::{app_err, Error, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    exec::eval::PluginEvalOutput, lock::checksum_dir, version::flint_version, Plugin, PluginKind,
+};
+use crate::util::toml::Config;
+
+pub const CACHE_DIR: &str = ".flint/cache";
+
+/// Dependency and build directories, left out of a plugin's input in projects with
+/// neither a git repo nor a .gitignore to tell which files are the project's own.
+/// Otherwise they count like any other directory, sources can be tracked in them.
+const DEFAULT_EXCLUDES: [&str; 8] = [
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "vendor",
+    ".venv",
+    "venv",
+    "__pycache__",
+];
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    output: PluginEvalOutput,
+}
+
+/// Eval outputs of earlier runs, so an unchanged plugin doesn't have to run again.
+/// Only the latest result of each plugin is kept.
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    pub fn new(project_dir: &Path) -> Self {
+        Self {
+            dir: project_dir.join(CACHE_DIR),
+        }
+    }
+
+    fn entry_path(&self, plugin: &Plugin) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{}.json",
+            plugin.kind.to_string(),
+            plugin.details.id
+        ))
+    }
+
+    /// The cached output, if there is one for this key. Entries that can't be read
+    /// (e.g. written by another version of flint) count as missing.
+    pub fn get(&self, plugin: &Plugin, key: &str) -> Option<PluginEvalOutput> {
+        let contents = fs::read_to_string(self.entry_path(plugin)).ok()?;
+        let entry = serde_json::from_str::<CacheEntry>(&contents).ok()?;
+        (entry.key == key).then_some(entry.output)
+    }
+
+    pub fn put(&self, plugin: &Plugin, key: &str, output: &PluginEvalOutput) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let entry = CacheEntry {
+            key: key.to_string(),
+            output: output.clone(),
+        };
+        fs::write(self.entry_path(plugin), serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+/// Hashes everything a plugin's result depends on: the plugin itself, its config,
+/// the files it was asked to check (with `--changed`), and the contents of its
+/// input files (as listed by `input_files`)
+pub fn cache_key(
+    plugin: &Plugin,
+    toml: &Config,
+    project_dir: &Path,
+    files: Option<&[String]>,
+    input_files: &[String],
+) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut field = |value: &[u8]| {
+        hasher.update((value.len() as u64).to_le_bytes());
+        hasher.update(value);
+    };
+
+    field(flint_version().to_string().as_bytes());
+    field(plugin.kind.to_string().as_bytes());
+    field(plugin.details.id.as_bytes());
+    field(plugin.details.version.as_bytes());
+    // Catches edits to local plugins, which rarely bump their version
+    field(checksum_dir(&plugin.path)?.as_bytes());
+    // What resolved_config is made of. Schema defaults are covered by the plugin's
+    // checksum, so there's no need to run its details.lua for them.
+    field(&serde_json::to_vec(&plugin.raw_config(toml))?);
+    field(&serde_json::to_vec(&toml.rules.get("common"))?);
+    if plugin.kind == PluginKind::Lint {
+        field(&serde_json::to_vec(&toml.config.get(&plugin.details.id))?);
+    }
+    field(&serde_json::to_vec(&files)?);
+
+    for file in input_files {
+        field(file.as_bytes());
+        field(&fs::read(project_dir.join(file))?);
+    }
+
+    let digest = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    Ok(format!("sha256:{}", digest))
+}
+
+/// Files under the project with one of the plugin's extensions or matching its
+/// `include` globs, minus the ones matching its `exclude` globs. Files ignored by
+/// git are left out, as are the directories in `DEFAULT_EXCLUDES` when there are no
+/// ignore rules. Paths are relative to the project and sorted.
+pub fn input_files(plugin: &Plugin, toml: &Config, project_dir: &Path) -> Result<Vec<String>> {
+    let mut include = plugin.string_list(toml, "include")?;
+    include.extend(
+        plugin
+            .details
+            .extensions
+            .iter()
+            .map(|extension| format!("**/*.{}", extension)),
+    );
+    let include = glob_set(plugin, &include)?;
+    let exclude = glob_set(plugin, &plugin.string_list(toml, "exclude")?)?;
+
+    let relative = |path: &Path| {
+        path.strip_prefix(project_dir)
+            .unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+
+    let walk_root = project_dir.to_path_buf();
+    let default_excludes = !has_ignore_rules(project_dir);
+    let walker = WalkBuilder::new(project_dir)
+        .hidden(false)
+        // Projects that aren't git repos can still have a .gitignore
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let name = entry.file_name();
+            if default_excludes
+                && entry.file_type().is_some_and(|kind| kind.is_dir())
+                && DEFAULT_EXCLUDES.iter().any(|dir| name == *dir)
+            {
+                return false;
+            }
+            // Excluding a directory excludes everything in it
+            let excluded = entry.path() != walk_root
+                && exclude.is_match(
+                    entry
+                        .path()
+                        .strip_prefix(&walk_root)
+                        .unwrap_or(entry.path()),
+                );
+            name != ".git" && name != ".flint" && !excluded
+        })
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|err| Error::Err(err.to_string()))?;
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let path = relative(entry.path());
+        if include.is_match(&path) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Whether git or a .gitignore decides which files under the project are its own
+fn has_ignore_rules(project_dir: &Path) -> bool {
+    let project_dir = project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| project_dir.to_path_buf());
+    project_dir.join(".gitignore").exists()
+        || project_dir.ancestors().any(|dir| dir.join(".git").exists())
+}
+
+fn glob_set(plugin: &Plugin, patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // "dist/" means the directory, like in .gitignore
+        let glob = GlobBuilder::new(pattern.trim_end_matches('/'))
+            .literal_separator(true)
+            .build();
+        match glob {
+            Ok(glob) => builder.add(glob),
+            Err(err) => {
+                return app_err!(
+                    "Invalid glob {} in plugin {}: {}",
+                    pattern,
+                    plugin.details.id,
+                    err
+                )
+            }
+        };
+    }
+
+    match builder.build() {
+        Ok(set) => Ok(set),
+        Err(err) => app_err!("Invalid globs in plugin {}: {}", plugin.details.id, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::PluginDetails;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// A js lint plugin and a project dir with the given files
+    fn setup(files: &[&str]) -> (TempDir, Plugin, Config, PathBuf) {
+        let tmp = TempDir::new().unwrap();
+        let plugin_dir = tmp.path().join("x-lint");
+        fs::create_dir_all(&plugin_dir).unwrap();
+        fs::write(plugin_dir.join("details.lua"), "").unwrap();
+        let plugin = Plugin {
+            details: PluginDetails {
+                id: "x-lint".to_string(),
+                extensions: vec!["js".to_string()],
+                version: "1.0.0".to_string(),
+                author: String::new(),
+                api_version: None,
+                flint_version: None,
+            },
+            path: plugin_dir,
+            kind: PluginKind::Lint,
+        };
+        let toml = toml::from_str("[flint]\nversion = 1\n").unwrap();
+
+        let project = tmp.path().join("project");
+        for file in files {
+            let path = project.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "let x = 1;").unwrap();
+        }
+        (tmp, plugin, toml, project)
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=flint",
+                "-c",
+                "user.email=flint@example.com",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn key(plugin: &Plugin, toml: &Config, project: &Path) -> String {
+        let files = input_files(plugin, toml, project).unwrap();
+        cache_key(plugin, toml, project, None, &files).unwrap()
+    }
+
+    #[test]
+    fn leaves_out_dependency_dirs_without_ignore_rules() {
+        let (_tmp, plugin, toml, project) =
+            setup(&["src/a.js", "node_modules/dep/index.js", "build/out.js"]);
+        assert_eq!(
+            input_files(&plugin, &toml, &project).unwrap(),
+            vec!["src/a.js"]
+        );
+    }
+
+    #[test]
+    fn follows_gitignore_outside_git() {
+        let (_tmp, plugin, toml, project) = setup(&["src/a.js", "out/a.js", "build/b.js"]);
+        fs::write(project.join(".gitignore"), "out/\n").unwrap();
+        assert_eq!(
+            input_files(&plugin, &toml, &project).unwrap(),
+            vec!["build/b.js", "src/a.js"]
+        );
+    }
+
+    #[test]
+    fn tracked_files_in_build_dirs_change_the_key() {
+        let (_tmp, plugin, toml, project) = setup(&["src/a.js", "build/foo.js"]);
+        git(&project, &["init", "--quiet"]);
+        git(&project, &["add", "-A"]);
+        git(&project, &["commit", "--quiet", "-m", "init"]);
+
+        assert_eq!(
+            input_files(&plugin, &toml, &project).unwrap(),
+            vec!["build/foo.js", "src/a.js"]
+        );
+        let before = key(&plugin, &toml, &project);
+        fs::write(project.join("build/foo.js"), "let x = 2;").unwrap();
+        assert_ne!(key(&plugin, &toml, &project), before);
+    }
+
+    #[test]
+    fn key_is_stable_and_follows_the_config() {
+        let (_tmp, plugin, toml, project) = setup(&["src/a.js"]);
+        let before = key(&plugin, &toml, &project);
+        assert_eq!(key(&plugin, &toml, &project), before);
+
+        let toml: Config =
+            toml::from_str("[flint]\nversion = 1\n[rules.x-lint]\nstrict = true\n").unwrap();
+        assert_ne!(key(&plugin, &toml, &project), before);
+    }
+}
@@ -0,0 +1,149 @@
+use flint_utils::{app_err, cmd, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{version::PLUGIN_API_VERSION, PluginKind};
+
+macro_rules! template {
+    ($path:literal) => {
+        ($path, include_str!(concat!("templates/", $path)))
+    };
+}
+
+// Paths are relative to templates/ and keep the kind as their first component,
+// which is stripped when the files are written out. Placeholders in paths are filled in too.
+const LINT_TEMPLATES: &[(&str, &str)] = &[
+    template!("lint/details.lua"),
+    template!("lint/run.lua"),
+    template!("lint/generate.lua"),
+    template!("lint/validate.lua"),
+    template!("lint/example/flint.toml"),
+    template!("lint/example/src/example.js"),
+    template!("lint/fixtures/basic/flint.toml"),
+    template!("lint/fixtures/basic/stdout.txt"),
+    template!("lint/fixtures/basic/status"),
+    template!("lint/fixtures/basic/expected_run.json"),
+    template!("lint/fixtures/basic/expected_eval.json"),
+    template!("lint/fixtures/basic/expected_generate/.{{id}}.json"),
+];
+
+const TEST_TEMPLATES: &[(&str, &str)] = &[
+    template!("test/details.lua"),
+    template!("test/run.lua"),
+    template!("test/generate.lua"),
+    template!("test/validate.lua"),
+    template!("test/example/flint.toml"),
+    template!("test/example/results.tap"),
+    template!("test/fixtures/basic/flint.toml"),
+    template!("test/fixtures/basic/stdout.txt"),
+    template!("test/fixtures/basic/status"),
+    template!("test/fixtures/basic/expected_run.json"),
+    template!("test/fixtures/basic/expected_eval.json"),
+];
+
+const CI_TEMPLATES: &[(&str, &str)] = &[
+    template!("ci/details.lua"),
+    template!("ci/generate.lua"),
+    template!("ci/validate.lua"),
+    template!("ci/example/flint.toml"),
+    template!("ci/fixtures/basic/flint.toml"),
+    template!("ci/fixtures/basic/dependencies.json"),
+    template!("ci/fixtures/basic/env.json"),
+    template!("ci/fixtures/basic/expected_generate/{{id}}.yml"),
+];
+
+const REPORT_TEMPLATES: &[(&str, &str)] = &[
+    template!("report/details.lua"),
+    template!("report/run.lua"),
+    template!("report/validate.lua"),
+    template!("report/example/flint.toml"),
+    template!("report/example/sample-lint/details.lua"),
+    template!("report/example/sample-lint/run.lua"),
+    template!("report/example/sample-lint/generate.lua"),
+    template!("report/example/sample-lint/validate.lua"),
+    template!("report/fixtures/basic/flint.toml"),
+    template!("report/fixtures/basic/report_input.json"),
+    template!("report/fixtures/basic/expected_report/reports/{{id}}-sample-lint.txt"),
+];
+
+const README_TEMPLATE: &str = include_str!("templates/README.md");
+
+/// Plugin ids end up in paths and TOML keys, so keep them simple
+pub fn validate_id(id: &str) -> Result<()> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        && id.starts_with(|c: char| c.is_ascii_lowercase());
+
+    if !valid {
+        return app_err!(
+            "Invalid plugin id \"{}\". Ids must start with a lowercase letter and contain only lowercase letters, digits, '-' and '_'",
+            id
+        );
+    }
+    Ok(())
+}
+
+/// Writes a skeleton plugin of the given kind to `dest`, returning the files it created
+pub fn scaffold(kind: &PluginKind, id: &str, dest: &Path) -> Result<Vec<PathBuf>> {
+    validate_id(id)?;
+
+    let is_empty = fs::read_dir(dest)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true);
+    if !is_empty {
+        return app_err!("{} already exists and is not empty", dest.display());
+    }
+
+    let templates = match kind {
+        PluginKind::Lint => LINT_TEMPLATES,
+        PluginKind::Test => TEST_TEMPLATES,
+        PluginKind::Ci => CI_TEMPLATES,
+        PluginKind::Report => REPORT_TEMPLATES,
+    };
+
+    let command = match kind {
+        PluginKind::Ci => "generate",
+        _ => "test",
+    };
+
+    let author = cmd!["git", "config", "user.name"]
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+
+    let api_version = format!("^{}.{}", PLUGIN_API_VERSION.major, PLUGIN_API_VERSION.minor);
+    let render = |template: &str| {
+        template
+            .replace("{{id}}", id)
+            .replace("{{kind}}", &kind.to_string())
+            .replace("{{author}}", &author)
+            .replace("{{api_version}}", &api_version)
+            .replace("{{command}}", command)
+    };
+
+    let files = templates
+        .iter()
+        .map(|(path, contents)| {
+            let relative = path.split_once('/').map(|(_, rest)| rest).unwrap_or(path);
+            (dest.join(render(relative)), render(contents))
+        })
+        .chain(std::iter::once((
+            dest.join("README.md"),
+            render(README_TEMPLATE),
+        )));
+
+    let mut created = Vec::new();
+    for (path, contents) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        created.push(path);
+    }
+
+    Ok(created)
+}
@@ -0,0 +1,559 @@
+use flint_utils::{warn, Result};
+use std::{
+    collections::{HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Condvar, Mutex},
+};
+use threadpool::ThreadPool;
+
+use super::{
+    verdict::{PluginOutcome, Verdict},
+    Plugin, PluginKind,
+};
+use crate::util::toml::{Config, ThresholdsConfig};
+
+/// A lint or test plugin waiting to be run, with the `after` and `resources`
+/// keys from its table in flint.toml
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub plugin: Plugin,
+    /// Ids of plugins that have to finish (and pass) before this one starts
+    pub after: Vec<String>,
+    /// Names of resources this plugin can't share with other plugins while it runs
+    pub resources: Vec<String>,
+    /// Set when the plugin's scheduling keys are invalid, the job then fails without running
+    pub error: Option<String>,
+}
+
+impl Job {
+    pub fn from_config(plugin: &Plugin, toml: &Config) -> Self {
+        let (after, resources, error) =
+            match (plugin.string_list(toml, "after"), resources(plugin, toml)) {
+                (Ok(after), Ok(resources)) => (after, resources, None),
+                (Err(err), _) | (_, Err(err)) => (vec![], vec![], Some(err.to_string())),
+            };
+
+        Self {
+            plugin: plugin.clone(),
+            after,
+            resources,
+            error,
+        }
+    }
+
+    fn id(&self) -> &str {
+        &self.plugin.details.id
+    }
+
+    fn key(&self) -> JobKey {
+        (self.plugin.kind.clone(), self.plugin.details.id.clone())
+    }
+}
+
+/// A lint and a test plugin can have the same id, so jobs are told apart by both
+type JobKey = (PluginKind, String);
+
+/// The resources a plugin declares with `resources = [...]`
+pub fn resources(plugin: &Plugin, toml: &Config) -> Result<Vec<String>> {
+    plugin.string_list(toml, "resources")
+}
+
+/// Named resources (e.g. "database") that only one plugin may hold at a time.
+/// Sets of resources are taken all at once, so two plugins can never end up
+/// waiting on each other.
+#[derive(Debug, Default)]
+pub struct Resources {
+    held: Mutex<HashSet<String>>,
+    released: Condvar,
+}
+
+/// Gives the resources back when dropped
+pub struct ResourceGuard {
+    resources: Arc<Resources>,
+    names: Vec<String>,
+}
+
+impl Drop for ResourceGuard {
+    fn drop(&mut self) {
+        let mut held = self.resources.held.lock().unwrap();
+        for name in &self.names {
+            held.remove(name);
+        }
+        self.resources.released.notify_all();
+    }
+}
+
+impl Resources {
+    pub fn try_acquire(self: &Arc<Self>, names: &[String]) -> Option<ResourceGuard> {
+        let mut held = self.held.lock().unwrap();
+        if names.iter().any(|name| held.contains(name)) {
+            return None;
+        }
+        held.extend(names.iter().cloned());
+        Some(self.guard(names))
+    }
+
+    /// Waits until none of the resources are held, then takes all of them
+    pub fn acquire(self: &Arc<Self>, names: &[String]) -> ResourceGuard {
+        let mut held = self.held.lock().unwrap();
+        while names.iter().any(|name| held.contains(name)) {
+            held = self.released.wait(held).unwrap();
+        }
+        held.extend(names.iter().cloned());
+        self.guard(names)
+    }
+
+    fn guard(self: &Arc<Self>, names: &[String]) -> ResourceGuard {
+        ResourceGuard {
+            resources: Arc::clone(self),
+            names: names.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Pending,
+    Running,
+    Done { passed: bool },
+}
+
+/// Runs the jobs in dependency order, with at most `max_jobs` at a time and never two
+/// jobs that share a resource. `run_job` is given the job's resources, which it may
+/// release early by dropping the guard. Every job's outcome is passed to `on_done`,
+/// including the ones that were skipped because a prerequisite didn't pass. A
+/// prerequisite id shared by several plugins has to pass for all of them.
+pub fn run<F, D>(
+    jobs: Vec<Job>,
+    max_jobs: usize,
+    thresholds: &ThresholdsConfig,
+    resources: Arc<Resources>,
+    run_job: F,
+    mut on_done: D,
+) where
+    F: Fn(&Plugin, ResourceGuard) -> PluginOutcome + Send + Sync + 'static,
+    D: FnMut(&Plugin, PluginOutcome),
+{
+    let ids = jobs
+        .iter()
+        .map(|job| job.id().to_string())
+        .collect::<HashSet<_>>();
+
+    let mut jobs = jobs;
+    for job in &mut jobs {
+        // Prerequisites that aren't part of this run (e.g. a test plugin during `flint test --lint`) are ignored
+        job.after.retain(|id| {
+            if !ids.contains(id) {
+                warn!(
+                    "{} should run after {}, which is not being run",
+                    job.plugin.details.id, id
+                );
+            }
+            ids.contains(id)
+        });
+    }
+
+    let mut states = jobs
+        .iter()
+        .map(|job| (job.key(), State::Pending))
+        .collect::<HashMap<_, _>>();
+    let mut keys_by_id = HashMap::<&str, Vec<JobKey>>::new();
+    for job in &jobs {
+        keys_by_id.entry(job.id()).or_default().push(job.key());
+    }
+    let prerequisite_is = |states: &HashMap<JobKey, State>, id: &str, passed: bool| {
+        let mut keys = keys_by_id[id].iter();
+        let done = |key| states[key] == State::Done { passed };
+        if passed {
+            keys.all(done)
+        } else {
+            keys.any(done)
+        }
+    };
+
+    let mut finish = |states: &mut HashMap<JobKey, State>, job: &Job, outcome: PluginOutcome| {
+        // Skipped plugins pass the verdict, but anything after them has to be skipped too
+        let passed = !matches!(outcome, PluginOutcome::Skipped(_))
+            && Verdict::from_outcomes(&[(job.id().to_string(), outcome.clone())], thresholds)
+                .passed();
+        states.insert(job.key(), State::Done { passed });
+        on_done(&job.plugin, outcome);
+    };
+
+    for job in &jobs {
+        if let Some(err) = &job.error {
+            finish(&mut states, job, PluginOutcome::Failed(err.clone()));
+        }
+    }
+
+    for cycle in find_cycles(&jobs) {
+        let message = format!("dependency cycle: {} -> {}", cycle.join(" -> "), cycle[0]);
+        for job in jobs
+            .iter()
+            .filter(|job| cycle.iter().any(|id| id == job.id()))
+        {
+            if states[&job.key()] == State::Pending {
+                finish(&mut states, job, PluginOutcome::Failed(message.clone()));
+            }
+        }
+    }
+
+    let run_job = Arc::new(run_job);
+    let pool = ThreadPool::new(max_jobs.max(1));
+    let (done_tx, done_rx) = mpsc::channel::<(JobKey, PluginOutcome)>();
+    let mut running = 0;
+
+    loop {
+        let mut changed = false;
+
+        for job in &jobs {
+            if states[&job.key()] != State::Pending {
+                continue;
+            }
+
+            let failed_prerequisite = job
+                .after
+                .iter()
+                .find(|id| prerequisite_is(&states, id, false));
+            if let Some(prerequisite) = failed_prerequisite {
+                let reason = format!("{} did not pass", prerequisite);
+                finish(&mut states, job, PluginOutcome::Skipped(reason));
+                changed = true;
+                continue;
+            }
+
+            let ready = job
+                .after
+                .iter()
+                .all(|id| prerequisite_is(&states, id, true));
+            if !ready || running >= max_jobs.max(1) {
+                continue;
+            }
+
+            let Some(guard) = resources.try_acquire(&job.resources) else {
+                continue;
+            };
+
+            states.insert(job.key(), State::Running);
+            running += 1;
+            changed = true;
+
+            let key = job.key();
+            let plugin = job.plugin.clone();
+            let run_job = Arc::clone(&run_job);
+            let done_tx = done_tx.clone();
+            pool.execute(move || {
+                let outcome = panic::catch_unwind(AssertUnwindSafe(|| run_job(&plugin, guard)))
+                    .unwrap_or_else(|_| PluginOutcome::Failed("plugin panicked".into()));
+                let _ = done_tx.send((key, outcome));
+            });
+        }
+
+        if running == 0 {
+            if !changed {
+                break;
+            }
+            continue;
+        }
+
+        let Ok((key, outcome)) = done_rx.recv() else {
+            break;
+        };
+        running -= 1;
+        if let Some(job) = jobs.iter().find(|job| job.key() == key) {
+            finish(&mut states, job, outcome);
+        }
+    }
+
+    // Shouldn't happen, but every job has to end up with an outcome
+    for job in &jobs {
+        if states[&job.key()] == State::Pending {
+            finish(
+                &mut states,
+                job,
+                PluginOutcome::Failed("could not be scheduled".to_string()),
+            );
+        }
+    }
+}
+
+/// Finds the groups of jobs that (directly or indirectly) have to run after themselves
+fn find_cycles(jobs: &[Job]) -> Vec<Vec<String>> {
+    // Plugins sharing an id can't be told apart in `after`, so they're one node
+    let mut after = HashMap::<&str, Vec<&String>>::new();
+    for job in jobs {
+        after.entry(job.id()).or_default().extend(&job.after);
+    }
+
+    let mut cycles = Vec::new();
+    let mut in_cycle = HashSet::new();
+    let mut visited = HashSet::new();
+
+    for job in jobs {
+        let mut path: Vec<&str> = Vec::new();
+        visit(
+            job.id(),
+            &after,
+            &mut path,
+            &mut visited,
+            &mut in_cycle,
+            &mut cycles,
+        );
+    }
+
+    cycles
+}
+
+fn visit<'a>(
+    id: &'a str,
+    after: &HashMap<&'a str, Vec<&'a String>>,
+    path: &mut Vec<&'a str>,
+    visited: &mut HashSet<&'a str>,
+    in_cycle: &mut HashSet<&'a str>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    if let Some(start) = path.iter().position(|other| *other == id) {
+        let cycle = &path[start..];
+        if cycle.iter().all(|id| !in_cycle.contains(id)) {
+            in_cycle.extend(cycle.iter().copied());
+            cycles.push(cycle.iter().map(|id| id.to_string()).collect());
+        }
+        return;
+    }
+    if !visited.insert(id) {
+        return;
+    }
+
+    path.push(id);
+    for next in after.get(id).into_iter().flat_map(|ids| ids.iter()) {
+        if let Some((next, _)) = after.get_key_value(next.as_str()) {
+            visit(next, after, path, visited, in_cycle, cycles);
+        }
+    }
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{
+        exec::eval::{LintPluginEvalOutput, PluginEvalOutput},
+        PluginDetails,
+    };
+    use std::{
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    fn lint_output(total_errors: u32) -> PluginOutcome {
+        PluginOutcome::Evaluated(PluginEvalOutput::Lint(LintPluginEvalOutput {
+            total_errors,
+            lint_results: vec![],
+        }))
+    }
+
+    /// Runs the jobs, with plugins whose id starts with "failing" reporting a lint error.
+    /// Returns the outcomes in the order they finished, and the plugins that actually ran.
+    fn run_jobs(
+        jobs: Vec<Job>,
+        max_jobs: usize,
+        run_job: impl Fn(&Plugin) + Send + Sync + 'static,
+    ) -> (Vec<(JobKey, PluginOutcome)>, Vec<JobKey>) {
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let ran_by_job = Arc::clone(&ran);
+        let mut outcomes = Vec::new();
+        run(
+            jobs,
+            max_jobs,
+            &ThresholdsConfig::default(),
+            Arc::new(Resources::default()),
+            move |plugin, _guard| {
+                run_job(plugin);
+                let key = (plugin.kind.clone(), plugin.details.id.clone());
+                ran_by_job.lock().unwrap().push(key);
+                lint_output(u32::from(plugin.details.id.starts_with("failing")))
+            },
+            |plugin, outcome| {
+                outcomes.push(((plugin.kind.clone(), plugin.details.id.clone()), outcome))
+            },
+        );
+        let ran = ran.lock().unwrap().clone();
+        (outcomes, ran)
+    }
+
+    /// How many of `run_job`'s calls overlapped at most
+    #[derive(Default)]
+    struct Overlap {
+        running: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    impl Overlap {
+        fn run(&self) {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(running, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(30));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn lint(id: &str) -> JobKey {
+        (PluginKind::Lint, id.to_string())
+    }
+
+    fn job(id: &str, after: &[&str]) -> Job {
+        kind_job(PluginKind::Lint, id, after)
+    }
+
+    fn kind_job(kind: PluginKind, id: &str, after: &[&str]) -> Job {
+        Job {
+            plugin: Plugin {
+                details: PluginDetails {
+                    id: id.to_string(),
+                    extensions: vec![],
+                    version: "1.0.0".to_string(),
+                    author: String::new(),
+                    api_version: None,
+                    flint_version: None,
+                },
+                path: PathBuf::from(id),
+                kind,
+            },
+            after: after.iter().map(|id| id.to_string()).collect(),
+            resources: vec![],
+            error: None,
+        }
+    }
+
+    #[test]
+    fn finds_no_cycles_in_a_chain() {
+        let jobs = [job("a", &["b"]), job("b", &["c"]), job("c", &[])];
+        assert!(find_cycles(&jobs).is_empty());
+    }
+
+    #[test]
+    fn finds_cycles() {
+        let jobs = [
+            job("a", &["b"]),
+            job("b", &["a"]),
+            job("c", &["c"]),
+            // Waits on a cycle, but isn't part of one
+            job("d", &["a"]),
+        ];
+        assert_eq!(
+            find_cycles(&jobs),
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["c".to_string()]
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_plugins_that_are_not_configured() {
+        let jobs = [job("a", &["missing"]), job("b", &["a"])];
+        assert!(find_cycles(&jobs).is_empty());
+    }
+
+    #[test]
+    fn skips_plugins_after_one_that_failed() {
+        let jobs = vec![
+            job("failing", &[]),
+            job("b", &["failing"]),
+            job("c", &["b"]),
+            job("d", &[]),
+        ];
+        let (outcomes, ran) = run_jobs(jobs, 2, |_| ());
+
+        let status = |id: &str| {
+            outcomes
+                .iter()
+                .find(|(key, _)| *key == lint(id))
+                .map(|(_, outcome)| outcome.status())
+        };
+        assert_eq!(status("failing"), Some("evaluated"));
+        assert_eq!(status("b"), Some("skipped"));
+        assert_eq!(status("c"), Some("skipped"));
+        assert_eq!(status("d"), Some("evaluated"));
+        assert!(!ran.contains(&lint("b")) && !ran.contains(&lint("c")));
+    }
+
+    #[test]
+    fn runs_plugins_after_their_prerequisites() {
+        let jobs = vec![job("c", &["b"]), job("b", &["a"]), job("a", &[])];
+        let (outcomes, ran) = run_jobs(jobs, 4, |_| ());
+
+        assert_eq!(ran, vec![lint("a"), lint("b"), lint("c")]);
+        assert_eq!(outcomes.len(), 3);
+    }
+
+    #[test]
+    fn runs_at_most_max_jobs_at_once() {
+        let overlap = Arc::new(Overlap::default());
+        let jobs = (0..6).map(|i| job(&format!("p{}", i), &[])).collect();
+        let overlap_in_jobs = Arc::clone(&overlap);
+        let (outcomes, _) = run_jobs(jobs, 2, move |_| overlap_in_jobs.run());
+
+        assert_eq!(outcomes.len(), 6);
+        assert!(overlap.max.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn never_runs_plugins_sharing_a_resource_at_once() {
+        let overlap = Arc::new(Overlap::default());
+        let jobs = (0..4)
+            .map(|i| Job {
+                resources: vec!["database".to_string()],
+                ..job(&format!("p{}", i), &[])
+            })
+            .collect();
+        let overlap_in_jobs = Arc::clone(&overlap);
+        let (outcomes, _) = run_jobs(jobs, 4, move |_| overlap_in_jobs.run());
+
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(overlap.max.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn fails_jobs_with_an_error_without_running_them() {
+        let jobs = vec![
+            Job {
+                error: Some("after should be a list of plugin ids".to_string()),
+                ..job("a", &[])
+            },
+            job("b", &["a"]),
+        ];
+        let (outcomes, ran) = run_jobs(jobs, 2, |_| ());
+
+        assert!(ran.is_empty());
+        assert!(matches!(
+            &outcomes[0],
+            (key, PluginOutcome::Failed(err)) if *key == lint("a") && err.starts_with("after should")
+        ));
+        assert!(matches!(&outcomes[1], (key, PluginOutcome::Skipped(_)) if *key == lint("b")));
+    }
+
+    #[test]
+    fn tells_plugins_with_the_same_id_apart() {
+        let jobs = vec![
+            kind_job(PluginKind::Lint, "x", &[]),
+            kind_job(PluginKind::Test, "x", &[]),
+            job("after-x", &["x"]),
+        ];
+        let (outcomes, mut ran) = run_jobs(jobs, 4, |_| ());
+
+        ran.sort();
+        assert_eq!(
+            ran,
+            vec![
+                lint("after-x"),
+                lint("x"),
+                (PluginKind::Test, "x".to_string())
+            ]
+        );
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[2].0, lint("after-x"));
+    }
+}
@@ -0,0 +1,376 @@
+use flint_ffi::add_ffi_modules;
+use flint_utils::Result;
+use mlua::{Function, Lua, LuaSerdeExt};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, ops::Range};
+use toml_edit::{Item, TableLike};
+
+use super::Plugin;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Integer,
+    #[serde(alias = "float")]
+    Number,
+    Boolean,
+    Array,
+    Table,
+    #[default]
+    Any,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::String => "string",
+            FieldType::Integer => "integer",
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+            FieldType::Array => "array",
+            FieldType::Table => "table",
+            FieldType::Any => "any",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single key in a plugin's config schema, as declared by `Schema()` in details.lua
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SchemaField {
+    #[serde(rename = "type", default)]
+    pub kind: FieldType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(rename = "enum")]
+    pub allowed: Option<Vec<serde_json::Value>>,
+    pub default: Option<serde_json::Value>,
+    pub description: Option<String>,
+    /// Schema for the elements of an array
+    pub items: Option<Box<SchemaField>>,
+    /// Schema for the keys of a table
+    pub fields: Option<BTreeMap<String, SchemaField>>,
+}
+
+pub type ConfigSchema = BTreeMap<String, SchemaField>;
+
+pub fn get_schema(plugin: &Plugin) -> Result<Option<ConfigSchema>> {
+    let lua = Lua::new();
+    add_ffi_modules(&lua)?;
+
+    plugin.load_file(&lua, "details.lua")?;
+
+    // Plugins aren't required to declare a schema
+    let schema_fn: Function = match lua.globals().get("Schema") {
+        Ok(func) => func,
+        Err(_) => return Ok(None),
+    };
+
+    let schema_value = schema_fn
+        .call::<mlua::Value>(())
+        .map_err(|err| plugin.runtime_error("details.lua", err))?;
+    let schema: ConfigSchema = lua.from_value(schema_value).map_err(|err| {
+        plugin.contract_error("details.lua", format!("invalid Schema() output: {}", err))
+    })?;
+    Ok(Some(schema))
+}
+
+/// Keys in a plugin's config table that flint reads itself (see `process` and
+/// `schedule`). They are accepted alongside the keys in the plugin's schema.
+pub fn reserved_fields() -> ConfigSchema {
+    let field = |kind: FieldType, description: &str| SchemaField {
+        kind,
+        description: Some(description.to_string()),
+        items: match kind {
+            FieldType::Array => Some(Box::new(SchemaField {
+                kind: FieldType::String,
+                ..Default::default()
+            })),
+            _ => None,
+        },
+        ..Default::default()
+    };
+
+    ConfigSchema::from([
+        (
+            "timeout".to_string(),
+            field(
+                FieldType::Number,
+                "Seconds the plugin's command may run for",
+            ),
+        ),
+        (
+            "kill_grace".to_string(),
+            field(
+                FieldType::Number,
+                "Seconds to wait for the command to stop after it times out",
+            ),
+        ),
+        (
+            "after".to_string(),
+            field(FieldType::Array, "Plugins that have to finish first"),
+        ),
+        (
+            "resources".to_string(),
+            field(
+                FieldType::Array,
+                "Shared resources (e.g. a database) only one plugin may use at a time",
+            ),
+        ),
+        (
+            "include".to_string(),
+            field(
+                FieldType::Array,
+                "Globs of extra files the plugin's result depends on",
+            ),
+        ),
+        (
+            "exclude".to_string(),
+            field(
+                FieldType::Array,
+                "Globs of files the plugin's result doesn't depend on",
+            ),
+        ),
+    ])
+}
+
+/// Fills in keys missing from a plugin's config with the defaults from its schema
+pub fn apply_defaults(config: &mut toml::Value, schema: &ConfigSchema) {
+    let Some(table) = config.as_table_mut() else {
+        return;
+    };
+
+    for (key, field) in schema {
+        match table.get_mut(key) {
+            Some(value) => {
+                if let Some(fields) = &field.fields {
+                    apply_defaults(value, fields);
+                }
+            }
+            None => {
+                let default = field
+                    .default
+                    .as_ref()
+                    .and_then(|default| toml::Value::try_from(default).ok());
+                if let Some(default) = default {
+                    table.insert(key.clone(), default);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Range<usize>>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Option<Range<usize>>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Option<Range<usize>>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+/// Checks a (span preserving) plugin config table against its schema.
+/// `path` is the dotted path of the table, used in messages.
+pub fn check_table(
+    table: &dyn TableLike,
+    table_span: Option<Range<usize>>,
+    schema: &ConfigSchema,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (key, field) in schema {
+        if field.required && table.get(key).is_none() {
+            diagnostics.push(Diagnostic::error(
+                format!("missing required key `{}` in `{}`", key, path),
+                table_span.clone(),
+            ));
+        }
+    }
+
+    for (key, item) in table.iter() {
+        let key_path = format!("{}.{}", path, key);
+        let key_span = table
+            .get_key_value(key)
+            .and_then(|(key, _)| key.span())
+            .or_else(|| item.span());
+
+        match schema.get(key) {
+            Some(field) => check_item(item, field, &key_path, diagnostics),
+            None => {
+                let mut message = format!("unknown key `{}`", key_path);
+                if let Some(suggestion) = closest_match(key, schema.keys()) {
+                    message.push_str(&format!(", did you mean `{}`?", suggestion));
+                }
+                diagnostics.push(Diagnostic::warning(message, key_span));
+            }
+        }
+    }
+}
+
+fn check_item(item: &Item, field: &SchemaField, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let span = item.span();
+
+    let matches = match field.kind {
+        FieldType::Any => true,
+        FieldType::String => item.is_str(),
+        FieldType::Integer => item.is_integer(),
+        FieldType::Number => item.is_integer() || item.is_float(),
+        FieldType::Boolean => item.is_bool(),
+        FieldType::Array => item.is_array() || item.is_array_of_tables(),
+        FieldType::Table => item.is_table_like(),
+    };
+
+    if !matches {
+        diagnostics.push(Diagnostic::error(
+            format!(
+                "`{}` should be of type {}, found {}",
+                path,
+                field.kind,
+                item_type_name(item)
+            ),
+            span,
+        ));
+        return;
+    }
+
+    if let Some(allowed) = &field.allowed {
+        let value = item
+            .as_value()
+            .and_then(|value| serde_json::to_value(toml_value(value)?).ok());
+        if let Some(value) = value {
+            if !allowed.contains(&value) {
+                let options = allowed
+                    .iter()
+                    .map(|option| option.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                diagnostics.push(Diagnostic::error(
+                    format!("`{}` must be one of {}, found {}", path, options, value),
+                    span.clone(),
+                ));
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (&field.items, item.as_array()) {
+        for (i, value) in array.iter().enumerate() {
+            let element = Item::Value(value.clone());
+            check_item(&element, items, &format!("{}[{}]", path, i), diagnostics);
+        }
+    }
+
+    if let (Some(fields), Some(table)) = (&field.fields, item.as_table_like()) {
+        check_table(table, span, fields, path, diagnostics);
+    }
+}
+
+fn item_type_name(item: &Item) -> &'static str {
+    if item.is_str() {
+        "string"
+    } else if item.is_integer() {
+        "integer"
+    } else if item.is_float() {
+        "float"
+    } else if item.is_bool() {
+        "boolean"
+    } else if item.is_datetime() {
+        "datetime"
+    } else if item.is_array() || item.is_array_of_tables() {
+        "array"
+    } else if item.is_table_like() {
+        "table"
+    } else {
+        "nothing"
+    }
+}
+
+fn toml_value(value: &toml_edit::Value) -> Option<toml::Value> {
+    toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+}
+
+/// Finds the closest candidate to `name` by edit distance, used for "did you mean" hints
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a String>,
+) -> Option<&'a String> {
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Optimal string alignment distance, i.e. levenshtein distance that also
+// counts swapping two adjacent characters as a single edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("timeout", "timeout"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("max_jobs", "max_job"), 1);
+    }
+
+    #[test]
+    fn edit_distance_counts_a_swap_as_one_edit() {
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        assert_eq!(edit_distance("timeuot", "timeout"), 1);
+    }
+}
@@ -0,0 +1,330 @@
+use std::{collections::BTreeSet, fs, path::Path};
+
+use super::exec::eval::{LintPluginEvalOutput, TestCaseOutput};
+
+pub const IGNORE_NEXT_LINE: &str = "flint-ignore-next-line";
+pub const IGNORE_FILE: &str = "flint-ignore-file";
+
+/// What a suppression comment covers
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    /// Results on this line
+    Line(u32),
+    File,
+}
+
+/// A `flint-ignore-next-line` or `flint-ignore-file` comment, one per `<plugin>:<rule>` it lists
+#[derive(Debug, Clone)]
+struct Suppression {
+    file: String,
+    /// Where the comment is
+    line: u32,
+    scope: Scope,
+    plugin: String,
+    /// `*` suppresses every rule of the plugin
+    rule: String,
+    used: bool,
+}
+
+impl Suppression {
+    fn covers(&self, result: &TestCaseOutput) -> bool {
+        let in_scope = match self.scope {
+            Scope::File => true,
+            Scope::Line(line) => result.line_no == Some(line),
+        };
+        let rule_matches = self.rule == "*" || result.rule().is_some_and(|rule| rule == self.rule);
+        in_scope && rule_matches && result.file_name == self.file
+    }
+}
+
+/// A plugin's lint output with the suppressed issues taken out
+#[derive(Debug, Clone)]
+pub struct Applied {
+    pub output: LintPluginEvalOutput,
+    /// How many of the plugin's issues were suppressed
+    pub suppressed: u32,
+    /// Suppressions for the plugin that didn't match any of its issues
+    pub unused: Vec<String>,
+    /// Suppression comments that were ignored because they are malformed
+    pub invalid: Vec<String>,
+}
+
+/// Takes the issues covered by suppression comments out of a lint plugin's output.
+/// `files` are the files the plugin checked, paths are relative to `project_dir`.
+/// Suppressions in those files and in any file the output mentions count.
+pub fn apply(
+    plugin_id: &str,
+    output: &LintPluginEvalOutput,
+    project_dir: &Path,
+    files: &[String],
+) -> Applied {
+    let files = files
+        .iter()
+        .cloned()
+        .chain(
+            output
+                .lint_results
+                .iter()
+                .map(|result| result.file_name.clone()),
+        )
+        .collect::<BTreeSet<_>>();
+
+    let mut suppressions = Vec::new();
+    let mut invalid = Vec::new();
+    for file in &files {
+        // Results don't always point at a readable file, e.g. when a plugin fails
+        let Ok(contents) = fs::read_to_string(project_dir.join(file)) else {
+            continue;
+        };
+        let (found, problems) = parse(file, &contents);
+        suppressions.extend(
+            found
+                .into_iter()
+                .filter(|suppression| suppression.plugin == plugin_id),
+        );
+        invalid.extend(problems);
+    }
+
+    let mut suppressed = 0;
+    let mut lint_results = Vec::new();
+    for result in &output.lint_results {
+        let covering = (!result.success)
+            .then(|| suppressions.iter_mut().find(|s| s.covers(result)))
+            .flatten();
+        match covering {
+            Some(suppression) => {
+                suppression.used = true;
+                suppressed += 1;
+            }
+            None => lint_results.push(result.clone()),
+        }
+    }
+
+    let unused = suppressions
+        .iter()
+        .filter(|suppression| !suppression.used)
+        .map(|suppression| {
+            format!(
+                "{}:{}: unused suppression {}:{}",
+                suppression.file, suppression.line, suppression.plugin, suppression.rule
+            )
+        })
+        .collect();
+
+    Applied {
+        output: LintPluginEvalOutput {
+            total_errors: output.total_errors.saturating_sub(suppressed),
+            lint_results,
+        },
+        suppressed,
+        unused,
+        invalid,
+    }
+}
+
+/// Finds the suppression comments in a file. Returns them along with the
+/// malformed ones, which don't suppress anything.
+fn parse(file: &str, contents: &str) -> (Vec<Suppression>, Vec<String>) {
+    let syntax = comment_syntax(file);
+    let lines = contents
+        .lines()
+        .map(|line| directive(line, syntax))
+        .collect::<Vec<_>>();
+
+    let mut suppressions = Vec::new();
+    let mut invalid = Vec::new();
+    for (index, found) in lines.iter().enumerate() {
+        let Some((keyword, rest)) = found else {
+            continue;
+        };
+        let line = index as u32 + 1;
+
+        let scope = if *keyword == IGNORE_FILE {
+            Scope::File
+        } else {
+            // Several suppressions for the same line can be stacked on top of each other
+            let target = lines[index + 1..]
+                .iter()
+                .position(|next| !matches!(next, Some((IGNORE_NEXT_LINE, _))))
+                .map_or(lines.len(), |offset| index + 1 + offset);
+            Scope::Line(target as u32 + 1)
+        };
+
+        let Some((targets, reason)) = rest.split_once("--") else {
+            invalid.push(format!(
+                "{}:{}: suppression without a reason, write it as `{} <plugin>:<rule> -- <reason>`",
+                file, line, keyword
+            ));
+            continue;
+        };
+        if reason.trim().is_empty() {
+            invalid.push(format!(
+                "{}:{}: suppression with an empty reason",
+                file, line
+            ));
+            continue;
+        }
+
+        for target in targets
+            .split([',', ' '])
+            .filter(|target| !target.is_empty())
+        {
+            match target.split_once(':') {
+                Some((plugin, rule)) if !plugin.is_empty() && !rule.is_empty() => suppressions
+                    .push(Suppression {
+                        file: file.to_string(),
+                        line,
+                        scope,
+                        plugin: plugin.to_string(),
+                        rule: rule.to_string(),
+                        used: false,
+                    }),
+                _ => invalid.push(format!(
+                    "{}:{}: {} should be <plugin>:<rule> (or <plugin>:* for every rule)",
+                    file, line, target
+                )),
+            }
+        }
+    }
+
+    (suppressions, invalid)
+}
+
+/// Comment markers: line comment prefixes and block comment delimiters
+type CommentSyntax = (
+    &'static [&'static str],
+    &'static [(&'static str, &'static str)],
+);
+
+const C_LIKE: CommentSyntax = (&["//"], &[("/*", "*/")]);
+const HASH: CommentSyntax = (&["#"], &[]);
+const DASHES: CommentSyntax = (&["--"], &[]);
+const MARKUP: CommentSyntax = (&[], &[("<!--", "-->")]);
+const CSS: CommentSyntax = (&[], &[("/*", "*/")]);
+const UNKNOWN: CommentSyntax = (&["//", "#"], &[]);
+
+fn comment_syntax(file: &str) -> CommentSyntax {
+    let extension = Path::new(file)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "rs" | "go" | "java" | "kt" | "kts"
+        | "swift" | "c" | "h" | "cpp" | "hpp" | "cc" | "cs" | "scala" | "dart" | "php" => C_LIKE,
+        "py" | "rb" | "sh" | "bash" | "zsh" | "yml" | "yaml" | "toml" | "r" | "pl" | "ex"
+        | "exs" | "dockerfile" => HASH,
+        "sql" | "lua" | "hs" => DASHES,
+        "html" | "htm" | "xml" | "md" | "vue" | "svelte" => MARKUP,
+        "css" | "scss" | "less" => CSS,
+        _ => UNKNOWN,
+    }
+}
+
+/// The suppression keyword and the rest of the comment, if the line is a suppression comment
+fn directive(line: &str, (prefixes, blocks): CommentSyntax) -> Option<(&'static str, &str)> {
+    let line = line.trim();
+    let comment = prefixes
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .or_else(|| {
+            blocks.iter().find_map(|(start, end)| {
+                let comment = line.strip_prefix(start)?;
+                Some(comment.strip_suffix(end).unwrap_or(comment))
+            })
+        })?
+        .trim();
+
+    [IGNORE_NEXT_LINE, IGNORE_FILE]
+        .into_iter()
+        .find_map(|keyword| {
+            let rest = comment.strip_prefix(keyword)?;
+            // flint-ignore-filename isn't a suppression
+            (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some((keyword, rest))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(suppressions: &[Suppression]) -> Vec<(u32, Scope, String)> {
+        suppressions
+            .iter()
+            .map(|s| (s.line, s.scope, format!("{}:{}", s.plugin, s.rule)))
+            .collect()
+    }
+
+    #[test]
+    fn parses_next_line_suppressions() {
+        let contents = "\
+fn main() {
+    // flint-ignore-next-line clippy:needless_return -- early exit reads better
+    return;
+}";
+        let (found, invalid) = parse("src/main.rs", contents);
+        assert!(invalid.is_empty());
+        assert_eq!(
+            targets(&found),
+            vec![(2, Scope::Line(3), "clippy:needless_return".to_string())]
+        );
+    }
+
+    #[test]
+    fn stacked_suppressions_cover_the_same_line() {
+        let contents = "\
+# flint-ignore-next-line ruff:E501 -- generated
+# flint-ignore-next-line pylint:* -- generated
+x = 1";
+        let (found, invalid) = parse("gen.py", contents);
+        assert!(invalid.is_empty());
+        assert_eq!(
+            targets(&found),
+            vec![
+                (1, Scope::Line(3), "ruff:E501".to_string()),
+                (2, Scope::Line(3), "pylint:*".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_file_suppressions_with_several_targets() {
+        let contents =
+            "<!-- flint-ignore-file markdownlint:MD013, markdownlint:MD033 -- copied -->";
+        let (found, invalid) = parse("README.md", contents);
+        assert!(invalid.is_empty());
+        assert_eq!(
+            targets(&found),
+            vec![
+                (1, Scope::File, "markdownlint:MD013".to_string()),
+                (1, Scope::File, "markdownlint:MD033".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_malformed_suppressions() {
+        let contents = "\
+-- flint-ignore-next-line sqlfluff:L001
+-- flint-ignore-next-line sqlfluff:L001 --
+-- flint-ignore-file sqlfluff -- legacy
+SELECT 1;";
+        let (found, invalid) = parse("query.sql", contents);
+        assert!(found.is_empty());
+        assert_eq!(invalid.len(), 3);
+        assert!(invalid[0].starts_with("query.sql:1: suppression without a reason"));
+        assert!(invalid[1].starts_with("query.sql:2: suppression with an empty reason"));
+        assert!(invalid[2].starts_with("query.sql:3: sqlfluff should be <plugin>:<rule>"));
+    }
+
+    #[test]
+    fn ignores_comments_that_are_not_suppressions() {
+        let contents = "\
+// flint-ignore-filename eslint:* -- not a keyword
+const s = '// flint-ignore-file eslint:* -- in a string';
+# flint-ignore-file eslint:* -- not a comment in js";
+        let (found, invalid) = parse("index.js", contents);
+        assert!(found.is_empty());
+        assert!(invalid.is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+function Details()
+    return {
+        id = "{{id}}",
+        author = "{{author}}",
+        version = "0.1.0",
+        api_version = "{{api_version}}",
+        -- CI plugins don't handle any files themselves
+        extensions = {},
+    }
+end
+
+-- Optional, used by `flint check-config` and to fill in defaults
+function Schema()
+    return {
+        branch = { type = "string", default = "main", description = "Branch that triggers the pipeline" },
+    }
+end
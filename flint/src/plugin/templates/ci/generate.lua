@@ -0,0 +1,27 @@
+local yaml = require("yaml")
+
+-- Returns the CI config files to write, as a table of file names (relative to flint.toml) to contents.
+-- dependencies holds the packages every other configured plugin needs, keyed by package manager
+-- (e.g. dependencies.npm), and env holds the environment variables they need.
+function Generate(config, dependencies, env)
+    local steps = {}
+
+    if dependencies.npm and #dependencies.npm > 0 then
+        local packages = {}
+        for _, dep in ipairs(dependencies.npm) do
+            table.insert(packages, dep.name .. "@" .. dep.version)
+        end
+        table.insert(steps, { name = "Install dependencies", run = "npm install -g " .. table.concat(packages, " ") })
+    end
+
+    table.insert(steps, { name = "Run flint", run = "flint test", env = env })
+
+    local pipeline = {
+        on = { push = { branches = { config.branch } } },
+        steps = steps,
+    }
+
+    return {
+        ["{{id}}.yml"] = yaml.stringify(pipeline),
+    }
+end
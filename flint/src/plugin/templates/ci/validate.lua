@@ -0,0 +1,4 @@
+-- Returns true if the plugin's config in flint.toml is usable
+function Validate(config)
+    return type(config.branch) == "string"
+end
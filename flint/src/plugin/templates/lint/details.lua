@@ -0,0 +1,30 @@
+function Details()
+    return {
+        id = "{{id}}",
+        author = "{{author}}",
+        version = "0.1.0",
+        api_version = "{{api_version}}",
+        -- Files with these extensions are handled by this plugin
+        extensions = { "js", "ts", "py", "rs" },
+    }
+end
+
+-- Optional, used by `flint check-config` and to fill in defaults
+function Schema()
+    return {
+        keywords = { type = "array", items = { type = "string" }, default = { "TODO", "FIXME" } },
+        dir = { type = "string", default = "src", description = "Directory to search" },
+    }
+end
+
+-- Optional, packages the CI plugins need to install for this plugin to run
+function Dependencies()
+    return {}
+end
+
+-- Optional, the keys of [rules.common] that Generate() translates to the tool's own rules,
+-- with support = "partial" and a note for keys it can't fully honour. `flint generate`
+-- shows the keys that aren't listed as unsupported.
+function CommonRules()
+    return {}
+end
@@ -0,0 +1,6 @@
+function add(a, b) {
+    // TODO: handle strings
+    return a + b;
+}
+
+module.exports = { add };
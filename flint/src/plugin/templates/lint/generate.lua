@@ -0,0 +1,9 @@
+local json = require("json")
+
+-- Returns the config files to write for the underlying tool,
+-- as a table of file names (relative to flint.toml) to contents.
+function Generate(config)
+    return {
+        [".{{id}}.json"] = json.stringify({ keywords = config.keywords }),
+    }
+end
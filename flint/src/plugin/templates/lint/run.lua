@@ -0,0 +1,41 @@
+-- Returns the command to run, as a list of the program and its arguments.
+-- It is run in the directory containing flint.toml. Return a table instead to set more options:
+--   { cmd = "grep", args = { ... }, env = { NAME = "value" }, cwd = "src", stdin = "...", timeout = 60 }
+-- or a list of steps, which run in order until one fails (unless it sets continue_on_failure = true):
+--   { steps = { { name = "check", cmd = "grep", args = { ... } } } }
+-- config.files lists the files to check when running `flint test --changed`, and is nil otherwise.
+function Run(config)
+    local args = { "grep", "-rnHE", table.concat(config.keywords, "|") }
+    for _, path in ipairs(config.files or { config.dir }) do
+        table.insert(args, path)
+    end
+    return args
+end
+
+-- Turns the output of the command into lint results.
+-- output has the fields stdout, stderr, status and success. When Run() returns steps, these are
+-- from the last step, and output.steps.<name> has the same fields for every step.
+function Eval(output, config)
+    local results = {}
+
+    for line in output.stdout:gmatch("[^\n]+") do
+        local file_name, line_no, text = line:match("^(.-):(%d+):(.*)$")
+        if file_name then
+            local keyword = text:match("%u+")
+            table.insert(results, {
+                file_name = file_name,
+                line_no = tonumber(line_no),
+                success = false,
+                error_message = "Found a leftover " .. keyword .. " comment",
+                -- Identifies the kind of issue, e.g. in flint-baseline.json
+                rule_id = "leftover-" .. keyword:lower(),
+                data = { text = text },
+            })
+        end
+    end
+
+    return {
+        total_errors = #results,
+        lint_results = results,
+    }
+end
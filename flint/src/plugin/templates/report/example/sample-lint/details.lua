@@ -0,0 +1,9 @@
+function Details()
+    return {
+        id = "sample-lint",
+        author = "",
+        version = "0.1.0",
+        api_version = "{{api_version}}",
+        extensions = {},
+    }
+end
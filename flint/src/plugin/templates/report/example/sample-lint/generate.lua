@@ -0,0 +1,3 @@
+function Generate(config)
+    return {}
+end
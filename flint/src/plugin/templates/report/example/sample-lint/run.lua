@@ -0,0 +1,13 @@
+-- Always reports a single lint error, so there is something to report on
+function Run(config)
+    return { "echo", "example.js:2: missing semicolon" }
+end
+
+function Eval(output, config)
+    return {
+        total_errors = 1,
+        lint_results = {
+            { file_name = "example.js", line_no = 2, success = false, error_message = "missing semicolon" },
+        },
+    }
+end
@@ -0,0 +1,3 @@
+function Validate(config)
+    return true
+end
@@ -0,0 +1,28 @@
+local path = require("path")
+local eval = require("eval")
+
+-- Called with the results of every lint and test plugin after `flint test` evaluates them.
+-- options has the fields config, plugin_id (the plugin that produced the results), output, and
+-- cached (true when the plugin didn't run because its result in .flint/cache was still valid).
+-- Returns the files to write, as a table of file names (relative to flint.toml) to contents.
+function Run(options)
+    local config = options.config
+    local output = eval.get_output(options.output)
+    local lines = { "Results for " .. options.plugin_id .. (options.cached and " (cached)" or "") }
+
+    if eval.get_output_type(options.output) == eval.lint then
+        table.insert(lines, output.total_errors .. " lint errors")
+        for _, result in ipairs(output.lint_results) do
+            table.insert(lines, string.format("%s:%s %s", result.file_name, result.line_no or "?", result.error_message or ""))
+        end
+    else
+        table.insert(lines, string.format("%d/%d tests passed", output.tests_passed, output.total_tests))
+        for _, result in ipairs(output.test_results) do
+            table.insert(lines, (result.success and "PASS " or "FAIL ") .. result.file_name)
+        end
+    end
+
+    return {
+        [path.join(config.output_path, "{{id}}-" .. options.plugin_id .. ".txt")] = table.concat(lines, "\n") .. "\n",
+    }
+end
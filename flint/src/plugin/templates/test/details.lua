@@ -0,0 +1,27 @@
+function Details()
+    return {
+        id = "{{id}}",
+        author = "{{author}}",
+        version = "0.1.0",
+        api_version = "{{api_version}}",
+        -- Files with these extensions are handled by this plugin
+        extensions = { "js", "ts" },
+    }
+end
+
+-- Optional, used by `flint check-config` and to fill in defaults
+function Schema()
+    return {
+        command = {
+            type = "array",
+            items = { type = "string" },
+            default = { "npx", "tap", "--reporter=tap" },
+            description = "Command that runs the tests and prints TAP output",
+        },
+    }
+end
+
+-- Optional, packages the CI plugins need to install for this plugin to run
+function Dependencies()
+    return {}
+end
@@ -0,0 +1,5 @@
+-- Returns the config files to write for the underlying tool,
+-- as a table of file names (relative to flint.toml) to contents.
+function Generate(config)
+    return {}
+end
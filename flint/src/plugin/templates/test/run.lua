@@ -0,0 +1,50 @@
+-- Returns the command to run, as a list of the program and its arguments.
+-- It is run in the directory containing flint.toml. Return a table instead to set more options:
+--   { cmd = "npx", args = { ... }, env = { CI = "true" }, cwd = "packages/app", stdin = "...", timeout = 600 }
+-- or a list of steps, which run in order until one fails (unless it sets continue_on_failure = true):
+--   { steps = { { name = "install", cmd = "npm", args = { "ci" } }, { name = "test", cmd = "npx", args = { ... } } } }
+function Run(config)
+    return config.command
+end
+
+-- Turns the TAP output of the command into test results.
+-- output has the fields stdout, stderr, status and success. When Run() returns steps, these are
+-- from the last step, and output.steps.<name> has the same fields for every step.
+function Eval(output, config)
+    local results = {}
+    local passed = 0
+
+    for line in output.stdout:gmatch("[^\n]+") do
+        local success = true
+        local name = line:match("^ok%s+%d+%s*%-?%s*(.*)$")
+        if not name then
+            success = false
+            name = line:match("^not ok%s+%d+%s*%-?%s*(.*)$")
+        end
+
+        if name then
+            if success then
+                passed = passed + 1
+            end
+
+            table.insert(results, {
+                file_name = name,
+                success = success,
+                error_message = not success and ("Test failed: " .. name) or nil,
+                data = { name = name },
+            })
+        end
+    end
+
+    local percentage = 0
+    if #results > 0 then
+        percentage = passed / #results * 100
+    end
+
+    return {
+        tests_passed = passed,
+        total_tests = #results,
+        passing_percentage = percentage,
+        test_results = results,
+    }
+end
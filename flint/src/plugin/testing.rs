@@ -0,0 +1,43 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    sync::{Mutex, MutexGuard},
+};
+
+/// Held by tests that set global flags (or `FLINT_CACHE_DIR`), so they don't run at once
+static FLAGS: Mutex<()> = Mutex::new(());
+
+pub fn lock_flags() -> MutexGuard<'static, ()> {
+    FLAGS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Runs git in `dir` and returns its output, with an identity so commits work anywhere
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args([
+            "-c",
+            "user.name=flint",
+            "-c",
+            "user.email=flint@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Writes a lint plugin with every file flint requires to `dir`
+pub fn write_plugin(dir: &Path, id: &str, version: &str) {
+    fs::create_dir_all(dir).unwrap();
+    let details = format!(
+        "function Details()\n    return {{ id = \"{}\", author = \"\", version = \"{}\", extensions = {{ \"js\" }} }}\nend\n",
+        id, version
+    );
+    fs::write(dir.join("details.lua"), details).unwrap();
+    for file in ["validate.lua", "generate.lua", "run.lua"] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+}
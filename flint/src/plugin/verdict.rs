@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use super::exec::eval::PluginEvalOutput;
+use crate::util::toml::ThresholdsConfig;
+
+// Exit codes are bit flags, so a run that has both lint and test failures
+// still maps to a distinct code (e.g. 3 = lint + test).
+pub const EXIT_LINT_FAILED: i32 = 1;
+pub const EXIT_TEST_FAILED: i32 = 2;
+pub const EXIT_PLUGIN_ERROR: i32 = 4;
+
+/// The result of running a single lint or test plugin
+#[derive(Debug, Clone)]
+pub enum PluginOutcome {
+    Evaluated(PluginEvalOutput),
+    Failed(String),
+    /// The plugin's command was killed, so there's no output of its own
+    TimedOut {
+        timeout: Duration,
+    },
+    /// Not run because a plugin it has to run after didn't pass
+    Skipped(String),
+}
+
+impl PluginOutcome {
+    /// What happened to the plugin, for machine-readable output
+    pub fn status(&self) -> &'static str {
+        match self {
+            PluginOutcome::Evaluated(_) => "evaluated",
+            PluginOutcome::Failed(_) => "failed",
+            PluginOutcome::TimedOut { .. } => "timed_out",
+            PluginOutcome::Skipped(_) => "skipped",
+        }
+    }
+
+    /// Why the plugin didn't produce a result of its own
+    pub fn error(&self) -> Option<String> {
+        match self {
+            PluginOutcome::Failed(err) => Some(err.clone()),
+            PluginOutcome::TimedOut { timeout, .. } => {
+                Some(format!("timed out after {}s", timeout.as_secs_f64()))
+            }
+            PluginOutcome::Evaluated(_) | PluginOutcome::Skipped(_) => None,
+        }
+    }
+
+    /// The plugin's own result, a timeout is only reported through `error`
+    pub fn output(&self) -> Option<&PluginEvalOutput> {
+        match self {
+            PluginOutcome::Evaluated(output) => Some(output),
+            PluginOutcome::Failed(_)
+            | PluginOutcome::TimedOut { .. }
+            | PluginOutcome::Skipped(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Verdict {
+    pub lint_failures: Vec<String>,
+    pub test_failures: Vec<String>,
+    pub plugin_errors: Vec<String>,
+    /// Skipped plugins don't change the exit code, whatever they depend on already does
+    pub skipped: Vec<String>,
+}
+
+impl Verdict {
+    pub fn from_outcomes(
+        outcomes: &[(String, PluginOutcome)],
+        thresholds: &ThresholdsConfig,
+    ) -> Self {
+        let mut verdict = Verdict::default();
+
+        for (plugin_id, outcome) in outcomes {
+            let limits = thresholds.for_plugin(plugin_id);
+            match outcome {
+                PluginOutcome::Evaluated(PluginEvalOutput::Lint(output)) => {
+                    let max_errors = limits.max_lint_errors.unwrap_or(0);
+                    if output.total_errors > max_errors {
+                        verdict.lint_failures.push(format!(
+                            "{}: {} lint errors (max allowed: {})",
+                            plugin_id, output.total_errors, max_errors
+                        ));
+                    }
+                }
+                PluginOutcome::Evaluated(PluginEvalOutput::Test(output)) => {
+                    let failed_tests = output.total_tests.saturating_sub(output.tests_passed);
+                    let failed = match limits.min_passing_percentage {
+                        Some(min) => output.total_tests > 0 && output.passing_percentage < min,
+                        None => failed_tests > 0,
+                    };
+
+                    if failed {
+                        verdict.test_failures.push(format!(
+                            "{}: {}/{} tests passed ({:.2}%)",
+                            plugin_id,
+                            output.tests_passed,
+                            output.total_tests,
+                            output.passing_percentage
+                        ));
+                    }
+                }
+                PluginOutcome::Failed(err) => {
+                    if thresholds.fail_on_plugin_error {
+                        verdict
+                            .plugin_errors
+                            .push(format!("{}: {}", plugin_id, err));
+                    }
+                }
+                PluginOutcome::Skipped(reason) => {
+                    verdict
+                        .skipped
+                        .push(format!("{}: skipped, {}", plugin_id, reason));
+                }
+                // Thresholds can't apply to output the plugin never produced, so this counts as a plugin error
+                PluginOutcome::TimedOut { timeout, .. } => {
+                    if thresholds.fail_on_plugin_error {
+                        verdict.plugin_errors.push(format!(
+                            "{}: timed out after {}s",
+                            plugin_id,
+                            timeout.as_secs_f64()
+                        ));
+                    }
+                }
+            }
+        }
+
+        verdict
+    }
+
+    pub fn passed(&self) -> bool {
+        self.exit_code() == 0
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        let mut code = 0;
+        if !self.lint_failures.is_empty() {
+            code |= EXIT_LINT_FAILED;
+        }
+        if !self.test_failures.is_empty() {
+            code |= EXIT_TEST_FAILED;
+        }
+        if !self.plugin_errors.is_empty() {
+            code |= EXIT_PLUGIN_ERROR;
+        }
+        code
+    }
+}
@@ -0,0 +1,94 @@
+use flint_utils::{Error, Result};
+use semver::{Version, VersionReq};
+use std::path::Path;
+
+use super::PluginDetails;
+
+/// Version of the API plugins are written against (the lua globals flint calls,
+/// their arguments and return values, and the FFI modules). Bump the minor version
+/// for additive changes and the major version for anything that breaks plugins.
+///
+/// - 1.0: Details, Dependencies, Generate, Validate, Run, Eval
+/// - 1.1: optional Schema() in details.lua, schema defaults applied to the config
+/// - 1.2: Run() may return a table with cmd, args, env, cwd, stdin and timeout
+/// - 1.3: Run() may return { steps = ... }, Eval() gets every step's output in output.steps
+/// - 1.4: config.files in Run(), set to the changed files by `flint test --changed`
+/// - 1.5: lint results may set rule_id
+/// - 1.6: optional CommonRules() in details.lua
+/// - 1.7: output.truncated in Eval(), set when only part of the output could be read
+pub const PLUGIN_API_VERSION: Version = Version::new(1, 7, 0);
+
+pub fn flint_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("crate version is valid semver")
+}
+
+/// Checks the `api_version` and `flint_version` requirements a plugin declares in
+/// details.lua. Plugins that don't declare them are assumed to be compatible.
+pub fn check_compatibility(details: &PluginDetails, file: &Path) -> Result<()> {
+    let incompatible = |message: String| Error::PluginIncompatible {
+        plugin_id: details.id.clone(),
+        file: file.to_path_buf(),
+        message,
+    };
+
+    let requirements = [
+        (
+            "api_version",
+            &details.api_version,
+            PLUGIN_API_VERSION,
+            "plugin API",
+        ),
+        (
+            "flint_version",
+            &details.flint_version,
+            flint_version(),
+            "Flint",
+        ),
+    ];
+
+    for (key, requirement, current, name) in requirements {
+        let Some(requirement) = requirement else {
+            continue;
+        };
+
+        let req = VersionReq::parse(requirement).map_err(|err| {
+            incompatible(format!(
+                "{} = \"{}\" is not a valid semver requirement: {}",
+                key, requirement, err
+            ))
+        })?;
+
+        if req.matches(&current) {
+            continue;
+        }
+
+        let hint = if requires_newer(&req, &current) {
+            "Upgrade Flint to use this version of the plugin".to_string()
+        } else {
+            format!(
+                "Update the plugin with `flint update {}`, or pin an older version of Flint",
+                details.id
+            )
+        };
+
+        return Err(incompatible(format!(
+            "it requires {} {}, but this is {} {}. {}",
+            name, requirement, name, current, hint
+        )));
+    }
+
+    Ok(())
+}
+
+// Whether the requirement asks for something newer than what we have, as
+// opposed to the plugin being written for an older (incompatible) version
+fn requires_newer(req: &VersionReq, current: &Version) -> bool {
+    req.comparators.iter().any(|comparator| {
+        let minimum = Version::new(
+            comparator.major,
+            comparator.minor.unwrap_or(0),
+            comparator.patch.unwrap_or(0),
+        );
+        minimum > *current
+    })
+}
@@ -0,0 +1,64 @@
+use flint_utils::{get_flag, output};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::plugin::{exec::eval::PluginEvalOutput, PluginKind};
+
+/// What `--output json` and `--output ndjson` report while a command runs.
+/// Log lines are reported too, as `log` events.
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    PluginStarted {
+        plugin: &'a str,
+        kind: &'a PluginKind,
+    },
+    /// `status` is what happened to the plugin, e.g. `installed` or `timed_out`
+    PluginFinished {
+        plugin: &'a str,
+        kind: &'a PluginKind,
+        status: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+    },
+    /// The result of a lint or test plugin, after suppressions and the baseline
+    EvalResult {
+        plugin: &'a str,
+        cached: bool,
+        output: &'a PluginEvalOutput,
+    },
+    FileWritten {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        plugin: Option<&'a str>,
+        path: &'a Path,
+    },
+    /// A file `generate --dry-run` or `--check` would change
+    FileOutdated {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        plugin: Option<&'a str>,
+        path: &'a Path,
+        diff: &'a str,
+    },
+    /// A file `generate` didn't overwrite, because it was edited since it was generated
+    FileDrifted {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        plugin: Option<&'a str>,
+        path: &'a Path,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        plugin: Option<&'a str>,
+        message: &'a str,
+    },
+}
+
+pub fn emit(event: Event) {
+    // Most runs print text, so don't bother serializing for them
+    if !get_flag!(output).is_structured() {
+        return;
+    }
+    match serde_json::to_value(&event) {
+        Ok(event) => output::emit(event),
+        Err(err) => flint_utils::debug!("Failed to serialize {:?}: {}", event, err),
+    }
+}
@@ -0,0 +1,103 @@
+use flint_utils::{cmd, Result};
+use std::{collections::BTreeSet, path::Path};
+
+use crate::plugin::cache::{check_ref, git_stdout};
+
+/// Files under `project_dir` that were added or modified since `since` (a branch, tag
+/// or commit), including uncommitted and untracked files. Without `since`, only the
+/// uncommitted and untracked changes are returned. Deleted files are left out, and
+/// paths are relative to `project_dir`.
+pub fn changed_files(project_dir: &Path, since: Option<&str>) -> Result<Vec<String>> {
+    // Compare against where the branch forked off, so commits that landed on
+    // `since` afterwards don't count as changes
+    let base = match since {
+        Some(since) => {
+            check_ref(since)?;
+            git_stdout(
+                cmd!["git", "merge-base", since, "HEAD"]
+                    .current_dir(project_dir)
+                    .output()?,
+            )?
+        }
+        None => "HEAD".to_string(),
+    };
+
+    let changed = git_stdout(
+        cmd![
+            "git",
+            "diff",
+            "--name-only",
+            "--relative",
+            "--diff-filter=d",
+            "-z",
+            base,
+            "--"
+        ]
+        .current_dir(project_dir)
+        .output()?,
+    )?;
+    let untracked = git_stdout(
+        cmd!["git", "ls-files", "--others", "--exclude-standard", "-z"]
+            .current_dir(project_dir)
+            .output()?,
+    )?;
+
+    let files = changed
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|file| !file.is_empty())
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>();
+
+    Ok(files.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::testing::git;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, contents: &str) {
+        fs::write(dir.join(file), contents).unwrap();
+    }
+
+    /// A repo with a commit on main, and a branch with one more
+    fn setup() -> TempDir {
+        let tmp = TempDir::new().unwrap();
+        let repo = tmp.path();
+        git(repo, &["init", "--quiet", "-b", "main"]);
+        write(repo, "a.js", "1");
+        write(repo, "b.js", "1");
+        git(repo, &["add", "-A"]);
+        git(repo, &["commit", "--quiet", "-m", "main"]);
+        git(repo, &["checkout", "--quiet", "-b", "feature"]);
+        write(repo, "a.js", "2");
+        git(repo, &["commit", "--quiet", "-am", "feature"]);
+        tmp
+    }
+
+    #[test]
+    fn lists_changes_since_a_ref() {
+        let tmp = setup();
+        write(tmp.path(), "b.js", "2");
+        write(tmp.path(), "new.js", "1");
+
+        assert_eq!(
+            changed_files(tmp.path(), Some("main")).unwrap(),
+            vec!["a.js", "b.js", "new.js"]
+        );
+        assert_eq!(
+            changed_files(tmp.path(), None).unwrap(),
+            vec!["b.js", "new.js"]
+        );
+    }
+
+    #[test]
+    fn rejects_refs_that_look_like_options() {
+        let tmp = setup();
+        assert!(changed_files(tmp.path(), Some("--output=pwned")).is_err());
+        assert!(!tmp.path().join("pwned").exists());
+    }
+}
@@ -2,6 +2,8 @@ use std::path::Path;
 
 use crossterm::event::{Event, KeyCode, KeyEvent};
 
+pub mod events;
+pub mod git;
 pub mod lang;
 pub mod toml;
 
@@ -55,4 +57,5 @@ pub fn handle_global_flags(app_args: &AppArgs) {
     }
 
     set_flag!(no_install, app_args.no_install);
+    set_flag!(output, app_args.output);
 }
@@ -1,4 +1,4 @@
-use flint_utils::Result;
+use flint_utils::{get_flag, Result};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, path::PathBuf};
 use toml;
@@ -11,6 +11,10 @@ fn default_hashmap() -> HashMap<String, toml::Value> {
     HashMap::new()
 }
 
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FlintConfig {
     pub version: u8,
@@ -19,6 +23,90 @@ pub struct FlintConfig {
     pub env: Option<String>,
 }
 
+/// Limits for a single plugin, used to decide whether `flint test` passes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Thresholds {
+    /// Maximum number of lint errors allowed before the run fails (default: 0)
+    pub max_lint_errors: Option<u32>,
+    /// Minimum percentage of passing tests (default: every test must pass)
+    pub min_passing_percentage: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThresholdsConfig {
+    pub max_lint_errors: Option<u32>,
+    pub min_passing_percentage: Option<f32>,
+    #[serde(default = "default_true")]
+    pub fail_on_plugin_error: bool,
+
+    // Per plugin overrides, e.g. [thresholds.eslint]
+    #[serde(flatten)]
+    pub plugins: HashMap<String, Thresholds>,
+}
+
+impl Default for ThresholdsConfig {
+    fn default() -> Self {
+        Self {
+            max_lint_errors: None,
+            min_passing_percentage: None,
+            fail_on_plugin_error: true,
+            plugins: HashMap::new(),
+        }
+    }
+}
+
+impl ThresholdsConfig {
+    /// Resolves the thresholds for a plugin, falling back to the global values
+    pub fn for_plugin(&self, plugin_id: &str) -> Thresholds {
+        let overrides = self.plugins.get(plugin_id);
+        Thresholds {
+            max_lint_errors: overrides
+                .and_then(|t| t.max_lint_errors)
+                .or(self.max_lint_errors),
+            min_passing_percentage: overrides
+                .and_then(|t| t.min_passing_percentage)
+                .or(self.min_passing_percentage),
+        }
+    }
+}
+
+/// Where a plugin is installed from, e.g. `eslint = { git = "...", rev = "v2" }`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum PluginSource {
+    Git {
+        git: String,
+        /// Branch, tag or commit to install (defaults to `plugins_branch`)
+        rev: Option<String>,
+        /// Directory of the plugin inside the repo (defaults to `flint-plugins/<kind>/<id>`)
+        dir: Option<String>,
+    },
+    /// A local plugin directory, relative to flint.toml. It is used in place and never installed.
+    Path { path: PathBuf },
+}
+
+impl PluginSource {
+    /// The directory of a local plugin, resolved relative to flint.toml
+    pub fn local_path(&self) -> Option<PathBuf> {
+        match self {
+            PluginSource::Path { path } => Some(get_flag!(current_dir).join(path)),
+            PluginSource::Git { .. } => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PluginsConfig {
+    #[serde(default)]
+    pub sources: HashMap<String, PluginSource>,
+}
+
+impl PluginsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
     pub flint: FlintConfig,
@@ -36,6 +124,12 @@ pub struct Config {
 
     #[serde(default = "default_hashmap")]
     pub report: HashMap<String, toml::Value>,
+
+    #[serde(default)]
+    pub thresholds: ThresholdsConfig,
+
+    #[serde(default, skip_serializing_if = "PluginsConfig::is_empty")]
+    pub plugins: PluginsConfig,
 }
 
 impl Config {
@@ -63,6 +157,8 @@ impl Config {
             config: HashMap::new(),
             ci: HashMap::new(),
             report: HashMap::new(),
+            thresholds: ThresholdsConfig::default(),
+            plugins: PluginsConfig::default(),
         };
         Self::create(path, config)
     }
@@ -1 +1,2 @@
 pub mod logs;
+pub mod review;
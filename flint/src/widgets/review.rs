@@ -0,0 +1,252 @@
+use flint_macros::{ui, widget};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Padding, Paragraph, StatefulWidget},
+};
+use similar::{DiffTag, TextDiff};
+use std::path::PathBuf;
+
+/// What happens to a file a plugin wants to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Pending,
+    /// The generated contents were written
+    Accepted,
+    /// The current contents were kept
+    KeptOurs,
+    /// Left alone for now, it can still be decided on
+    Skipped,
+    /// The file already has the generated contents
+    Unchanged,
+}
+
+impl Decision {
+    fn label(&self) -> (&'static str, Color) {
+        match self {
+            Decision::Pending => ("[ ]", Color::Yellow),
+            Decision::Accepted => ("[a]", Color::Green),
+            Decision::KeptOurs => ("[o]", Color::Blue),
+            Decision::Skipped => ("[s]", Color::DarkGray),
+            Decision::Unchanged => ("[=]", Color::DarkGray),
+        }
+    }
+
+    /// Whether the file still needs a decision
+    pub fn is_open(&self) -> bool {
+        matches!(self, Decision::Pending | Decision::Skipped)
+    }
+}
+
+/// A file a plugin wants to write, and what the user decided to do with it
+#[derive(Debug, Clone)]
+pub struct ReviewFile {
+    pub plugin: String,
+    /// As the plugin named it, relative to flint.toml
+    pub name: String,
+    pub path: PathBuf,
+    /// None if the file doesn't exist yet
+    pub current: Option<String>,
+    pub generated: String,
+    /// Edited since flint last generated it
+    pub drifted: bool,
+    pub decision: Decision,
+}
+
+impl ReviewFile {
+    pub fn new(plugin: &str, name: &str, path: PathBuf, generated: String, drifted: bool) -> Self {
+        let current = std::fs::read_to_string(&path).ok();
+        let decision = if current.as_deref() == Some(generated.as_str()) {
+            Decision::Unchanged
+        } else {
+            Decision::Pending
+        };
+        Self {
+            plugin: plugin.to_string(),
+            name: name.to_string(),
+            path,
+            current,
+            generated,
+            drifted,
+            decision,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ReviewState {
+    list: ListState,
+    scroll: usize,
+}
+
+impl ReviewState {
+    pub fn selected(&self) -> Option<usize> {
+        self.list.selected()
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.list.select(Some(index));
+        self.scroll = 0;
+    }
+
+    pub fn select_next(&mut self, len: usize) {
+        let next = self.selected().map_or(0, |index| index + 1);
+        if next < len {
+            self.select(next);
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.selected().map_or(0, |index| index.saturating_sub(1));
+        self.select(previous);
+    }
+
+    pub fn scroll_down(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_add(amount);
+    }
+
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_sub(amount);
+    }
+}
+
+/// Lists the files plugins want to write, next to a side-by-side diff of the selected one
+pub struct ReviewWidget<'a> {
+    pub files: &'a [ReviewFile],
+}
+
+impl StatefulWidget for ReviewWidget<'_> {
+    type State = ReviewState;
+
+    fn render(self, area: Rect, buffer: &mut Buffer, state: &mut Self::State) {
+        if state.selected().is_none() && !self.files.is_empty() {
+            state.select(0);
+        }
+
+        let open = self
+            .files
+            .iter()
+            .filter(|file| file.decision.is_open())
+            .count();
+        let items = self
+            .files
+            .iter()
+            .map(|file| {
+                let (label, color) = match file.decision {
+                    Decision::Pending if file.drifted => ("[!]", Color::Red),
+                    decision => decision.label(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(label, Style::default().fg(color)),
+                    Span::raw(" "),
+                    Span::raw(file.name.clone()),
+                ]))
+            })
+            .collect::<Vec<_>>();
+
+        let list = widget!({
+            List::new(
+                items,
+                block: widget!({ Block::bordered(title: format!("Files [{} to review]", open)) }),
+                highlight_style: Style::default().add_modifier(Modifier::REVERSED)
+            )
+        });
+
+        let file = state.selected().and_then(|index| self.files.get(index));
+        let (current, generated) = match file {
+            Some(file) => side_by_side(file, state),
+            None => (
+                vec![Line::from("Waiting for plugins to generate files...")],
+                vec![],
+            ),
+        };
+        let generated_title = match file {
+            Some(file) => format!("Generated by {}", file.plugin),
+            None => "Generated".to_string(),
+        };
+        let current_title = match file {
+            Some(file) if file.current.is_none() => "Current (new file)",
+            Some(file) if file.drifted => "Current (edited since it was generated)",
+            _ => "Current",
+        };
+
+        let mut list_state = state.list.clone();
+        ui!((area, buffer) => {
+            Layout(
+                direction: Direction::Horizontal,
+                constraints: [Constraint::Percentage(25), Constraint::Fill(1)]
+            ) {
+                Stateful(&mut list_state) {
+                    { list }
+                },
+                Layout(
+                    direction: Direction::Horizontal,
+                    constraints: [Constraint::Percentage(50), Constraint::Percentage(50)]
+                ) {
+                    Paragraph::new(
+                        current,
+                        block: widget!({ Block::bordered(title: current_title, padding: Padding::horizontal(1)) })
+                    ),
+                    Paragraph::new(
+                        generated,
+                        block: widget!({ Block::bordered(title: generated_title, padding: Padding::horizontal(1)) })
+                    )
+                }
+            }
+        });
+        state.list = list_state;
+    }
+}
+
+/// The current and generated contents as rows of equal length, starting at the scroll position
+fn side_by_side(
+    file: &ReviewFile,
+    state: &mut ReviewState,
+) -> (Vec<Line<'static>>, Vec<Line<'static>>) {
+    let current = file.current.as_deref().unwrap_or_default();
+    let diff = TextDiff::from_lines(current, &file.generated);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+
+    let line = |lines: &[&str], index: usize, style: Style| {
+        let text = lines[index].trim_end_matches(['\n', '\r']);
+        Line::from(vec![
+            Span::styled(
+                format!("{:>4} ", index + 1),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(text.to_string(), style),
+        ])
+    };
+
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let (old_style, new_style) = match tag {
+            DiffTag::Equal => (Style::default(), Style::default()),
+            _ => (
+                Style::default().fg(Color::Red),
+                Style::default().fg(Color::Green),
+            ),
+        };
+        for row in 0..old_range.len().max(new_range.len()) {
+            left.push(match old_range.start + row {
+                index if index < old_range.end => line(old_lines, index, old_style),
+                _ => Line::default(),
+            });
+            right.push(match new_range.start + row {
+                index if index < new_range.end => line(new_lines, index, new_style),
+                _ => Line::default(),
+            });
+        }
+    }
+
+    state.scroll = state.scroll.min(left.len().saturating_sub(1));
+    (
+        left.split_off(state.scroll.min(left.len())),
+        right.split_off(state.scroll.min(right.len())),
+    )
+}